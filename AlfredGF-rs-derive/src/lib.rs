@@ -0,0 +1,148 @@
+#![allow(non_snake_case)] // the crate name
+#![allow(clippy::needless_return)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, LitInt, Meta, NestedMeta,
+};
+
+// implements AFVertex for a #[repr(C)] struct, each field being an attribute;
+// locations count up from 0 in field order unless given with #[location(n)],
+// and formats come from the field types unless given with #[vertex_format(Float2)],
+// which fails to compile if the field is smaller than the format
+#[proc_macro_derive(AFVertex, attributes(location, vertex_format))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+
+    return match vertex_impl(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    };
+}
+
+fn vertex_impl(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    if !is_repr_c(&input.attrs) {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "AFVertex needs #[repr(C)] for its offsets to match the layout the GPU reads",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "AFVertex can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "AFVertex can only be derived for structs",
+            ));
+        }
+    };
+
+    let name: &Ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let mut next_location: u32 = 0;
+    let mut attribs: Vec<TokenStream2> = Vec::new();
+    let mut size_checks: Vec<TokenStream2> = Vec::new();
+    for field in fields.iter() {
+        let field_name = field.ident.as_ref().unwrap();
+        let field_type = &field.ty;
+
+        let location: u32 = match find_argument(&field.attrs, "location")? {
+            Some(NestedMeta::Lit(syn::Lit::Int(location))) => {
+                LitInt::base10_parse::<u32>(&location)?
+            }
+            Some(argument) => {
+                return Err(Error::new_spanned(
+                    argument,
+                    "expected a location like #[location(0)]",
+                ));
+            }
+            None => next_location,
+        };
+        next_location = location + 1;
+
+        let vertex_format: TokenStream2 = match find_argument(&field.attrs, "vertex_format")? {
+            Some(NestedMeta::Meta(Meta::Path(format))) => {
+                quote! { ::AlfredGF_rs::enums::AFVertexFormat::#format }
+            }
+            Some(argument) => {
+                return Err(Error::new_spanned(
+                    argument,
+                    "expected a format like #[vertex_format(Float2)]",
+                ));
+            }
+            None => quote! {
+                <#field_type as ::AlfredGF_rs::generic::AFVertexAttribType>::VERTEX_FORMAT
+            },
+        };
+
+        // a format given by hand could otherwise read past the end of its field
+        let message: String = format!(
+            "the {} field of {} is smaller than its vertex format",
+            field_name, name
+        );
+        size_checks.push(quote! {
+            ::std::assert!(
+                ::std::mem::size_of::<#field_type>() >= (#vertex_format).size() as usize,
+                #message
+            );
+        });
+
+        attribs.push(quote! {
+            ::AlfredGF_rs::generic::AFVertexAttrib {
+                location: #location,
+                offset: ::std::mem::offset_of!(#name #type_generics, #field_name) as u32,
+                vertex_format: #vertex_format,
+            }
+        });
+    }
+
+    return Ok(quote! {
+        impl #impl_generics ::AlfredGF_rs::generic::AFVertex for #name #type_generics #where_clause {
+            const STRIDE: u64 = ::std::mem::size_of::<#name #type_generics>() as u64;
+            const ATTRIBS: &'static [::AlfredGF_rs::generic::AFVertexAttrib] = {
+                #(#size_checks)*
+                &[#(#attribs),*]
+            };
+        }
+    });
+}
+
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    return attrs.iter().any(|attr| match attr.parse_meta() {
+        Ok(Meta::List(list)) if list.path.is_ident("repr") => {
+            list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("C"),
+                _ => false,
+            })
+        }
+        _ => false,
+    });
+}
+
+// the single argument of a field attribute like #[location(0)], if the field has it
+fn find_argument(attrs: &[Attribute], name: &str) -> Result<Option<NestedMeta>, Error> {
+    let attr: &Attribute = match attrs.iter().find(|attr| attr.path.is_ident(name)) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+
+    return match attr.parse_meta()? {
+        Meta::List(list) if list.nested.len() == 1 => Ok(list.nested.into_iter().next()),
+        meta => Err(Error::new_spanned(
+            meta,
+            format!("expected a single argument like #[{}(...)]", name),
+        )),
+    };
+}
@@ -0,0 +1,154 @@
+extern crate AlfredGF_rs;
+
+use std::cell::Cell;
+
+use AlfredGF_rs::{constructors::*, enums::*, generic::*, implementation::*, util_structs::*};
+
+// draws a few frames into an offscreen target, without a window
+pub fn main() -> Result<(), AFError> {
+    let context_config: AFContextConfig = AFContextConfig {
+        vsync: false,
+        anisotropic_filtering: false,
+        backend_lib: AFBackendLibrary::Vulkan,
+        power_preference: AFPowerPreference::LowPower,
+        swap_chain_format: AFTextureFormat::Rgba8UnormSrgb,
+        depth_format: None,
+        sample_count: 1,
+        upload_chunk_size: 1 << 20,
+    };
+
+    let mut context: AFContext = AFContext::new(
+        AFSize2D {
+            width: 256,
+            height: 256,
+        },
+        &context_config,
+    )?;
+
+    let v_s_c: AFShaderConfig = AFShaderConfig {
+        stage: AFShaderStage::Vertex,
+        bytecode: include_bytes!("../render/shader.vert.spv"),
+        entry_point: "main",
+    };
+    let f_s_c: AFShaderConfig = AFShaderConfig {
+        stage: AFShaderStage::Fragment,
+        bytecode: include_bytes!("../render/shader.frag.spv"),
+        entry_point: "main",
+    };
+    let vertex_shader: AFShaderModule = AFShaderModule::new(&context, &v_s_c)?;
+    let fragment_shader: AFShaderModule = AFShaderModule::new(&context, &f_s_c)?;
+
+    let blend_descriptor: AFBlendDescriptor = AFBlendDescriptor {
+        src_factor: AFBlendFactor::One,
+        dst_factor: AFBlendFactor::Zero,
+        operation: AFBlendOperation::Add,
+    };
+
+    let pipeline_config: AFRenderPipelineConfig<AFShaderModule> = AFRenderPipelineConfig {
+        vertex_shader: &vertex_shader,
+        fragment_shader: Some(&fragment_shader),
+        // the shaders are only used to clear, but the pipeline still has to match them
+        uniforms: &[AFUniformGroup {
+            set: 0,
+            uniforms: &[AFUniform {
+                location: 0,
+                stage: AFShaderStage::Vertex,
+                dynamic: false,
+                byte_size: 4,
+                uniform_type: AFUniformType::Buffer,
+                slots: 1,
+            }],
+        }],
+        vertex_buffer_slots: &[AFVertexBuffer {
+            stride: 8,
+            step_mode: AFVertexStepMode::PerVertex,
+            attribs: &[AFVertexAttrib {
+                location: 0,
+                offset: 0,
+                vertex_format: AFVertexFormat::Float2,
+            }],
+        }],
+        colour_states: &[AFColourState {
+            format: AFTextureFormat::Rgba8UnormSrgb,
+            colour_blend: blend_descriptor,
+            alpha_blend: blend_descriptor,
+            write_mask: AFColourWriteMask::ALL,
+        }],
+        primitive: AFDrawablePrimitive::Triangles,
+        front_face: AFDirection::Clockwise,
+        cull_mode: None,
+        index_format: AFIndexFormat::UnsignedInt16,
+        depth_stencil: None,
+        sample_mask: !0,
+        alpha_to_coverage: false,
+        render_target: None,
+    };
+    let pipeline: AFRenderPipeline = AFRenderPipeline::new(&context, &pipeline_config)?;
+
+    let first_clear: AFRenderCommand = AFRenderCommand::new(
+        &pipeline,
+        &AFRenderCommandConfig {
+            colour: [1.0, 0.0, 0.0, 1.0],
+            clear_depth: 1.0,
+            clear_stencil: 0,
+            vertex_count: 0,
+            enabled_uniform_groups: 0..0,
+            calls: 1,
+            command_type: AFRenderCommandType::Empty,
+            dynamic_slots: Vec::new(),
+            blend_colour: [0.0, 0.0, 0.0, 0.0],
+            viewport: None,
+            scissor_rect: None,
+            stencil_reference: 0,
+        },
+    )?;
+    context.draw(&[first_clear])?;
+    let frame: AFOwnedImage = context.redraw_frame()?;
+    println!(
+        "read a {}x{} frame, first pixel {:?}",
+        frame.size.width,
+        frame.size.height,
+        &frame.data[..4]
+    );
+
+    let frames: Cell<u32> = Cell::new(0);
+
+    AFContext::mainloop(
+        context,
+        move |_context, _state| {
+            let clear_command: AFRenderCommand = AFRenderCommand::new(
+                &pipeline,
+                &AFRenderCommandConfig {
+                    colour: [0.1, 0.2, 0.3, 1.0],
+                    clear_depth: 1.0,
+                    clear_stencil: 0,
+                    vertex_count: 0,
+                    enabled_uniform_groups: 0..0,
+                    calls: 1,
+                    command_type: AFRenderCommandType::Empty,
+                    dynamic_slots: Vec::new(),
+                    blend_colour: [0.0, 0.0, 0.0, 0.0],
+                    viewport: None,
+                    scissor_rect: None,
+                    stencil_reference: 0,
+                },
+            )
+            .expect("Could not create the render command.");
+
+            frames.set(frames.get() + 1);
+
+            AFMainloopResult {
+                window_commands: match frames.get() > 3 {
+                    true => vec![AFWindowCommand::DestroyWindow],
+                    false => vec![],
+                },
+                render_commands: vec![clear_command],
+            }
+        },
+        move || {
+            println!("finished");
+        },
+    );
+
+    Ok(())
+}
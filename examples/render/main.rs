@@ -3,8 +3,17 @@ extern crate AlfredGF_rs;
 use AlfredGF_rs::generic::AFShaderConfig;
 use AlfredGF_rs::implementation::AFShaderModule;
 use AlfredGF_rs::{constructors::*, enums::*, generic::*, implementation::*, util_structs::*};
+use AlfredGF_rs_derive::AFVertex;
+use zerocopy::AsBytes;
 
-pub fn main() {
+// matches `layout(location = 0) in vec2 position` in shader.vert
+#[derive(Copy, Clone, AsBytes, AFVertex)]
+#[repr(C)]
+struct Vertex {
+    position: [f32; 2],
+}
+
+pub fn main() -> Result<(), AFError> {
     let size: AFWindowSize = AFWindowSize::Size(AFSize2D {
         width: 1280,
         height: 720,
@@ -22,11 +31,11 @@ pub fn main() {
         maximized: false,
         fullscreen: false,
         title: "Test Window",
-        monitor_chooser: Option::Some(|monitors: Vec<AFMonitor>| {
+        monitor_chooser: Option::Some(|_monitors: Vec<AFMonitor>| {
             0
         }),
     };
-    let window: AFWindow = AFWindow::new(&window_config);
+    let window: AFWindow = AFWindow::new(&window_config)?;
 
     let context_config: AFContextConfig = AFContextConfig {
         vsync: true,
@@ -54,9 +63,13 @@ pub fn main() {
             }
         },
         power_preference: AFPowerPreference::LowPower,
+        swap_chain_format: AFTextureFormat::Bgra8UnormSrgb,
+        depth_format: None,
+        sample_count: 1,
+        upload_chunk_size: 1 << 20,
     };
 
-    let context: AFContext = AFContext::new(window, &context_config);
+    let context: AFContext = AFContext::new(window, &context_config)?;
 
     let v_s_c: AFShaderConfig = AFShaderConfig {
         stage: AFShaderStage::Vertex,
@@ -68,8 +81,8 @@ pub fn main() {
         bytecode: include_bytes!("shader.frag.spv"),
         entry_point: "main",
     };
-    let vertex_shader: AFShaderModule = AFShaderModule::new(&context, &v_s_c);
-    let fragment_shader: AFShaderModule = AFShaderModule::new(&context, &f_s_c);
+    let vertex_shader: AFShaderModule = AFShaderModule::new(&context, &v_s_c)?;
+    let fragment_shader: AFShaderModule = AFShaderModule::new(&context, &f_s_c)?;
 
     let test_uniform: AFUniform = AFUniform {
         location: 0,
@@ -77,6 +90,7 @@ pub fn main() {
         dynamic: true,
         byte_size: 4, // 4 bytes per float
         uniform_type: AFUniformType::Buffer,
+        slots: 1,
     };
     let uniform_group: AFUniformGroup = AFUniformGroup {
         set: 0,
@@ -89,35 +103,117 @@ pub fn main() {
         operation: AFBlendOperation::Add,
     };
 
-    let position_attrib: AFVertexAttrib = AFVertexAttrib {
-        location: 0,
-        offset: 0,
-        vertex_format: AFVertexFormat::Float,
-    };
-    let position_buffer: AFVertexBuffer = AFVertexBuffer {
-        stride: 0,
-        step_mode: AFVertexStepMode::PerVertex,
-        attribs: &[position_attrib],
-    };
+    let position_buffer: AFVertexBuffer = Vertex::vertex_buffer(AFVertexStepMode::PerVertex);
 
-    let main_render_pipeline_config: AFRenderPipelineConfig = AFRenderPipelineConfig {
+    let main_render_pipeline_config: AFRenderPipelineConfig<AFShaderModule> = AFRenderPipelineConfig {
+        vertex_shader: &vertex_shader,
+        fragment_shader: Some(&fragment_shader),
         uniforms: &[uniform_group],
         vertex_buffer_slots: &[position_buffer],
-        colour_blend: blend_descriptor,
-        alpha_blend: blend_descriptor,
+        colour_states: &[AFColourState {
+            format: AFTextureFormat::Bgra8UnormSrgb,
+            colour_blend: blend_descriptor,
+            alpha_blend: blend_descriptor,
+            write_mask: AFColourWriteMask::ALL,
+        }],
         primitive: AFDrawablePrimitive::Triangles,
         front_face: AFDirection::Clockwise,
         cull_mode: None,
         index_format: AFIndexFormat::UnsignedInt16,
+        depth_stencil: None,
+        sample_mask: !0,
+        alpha_to_coverage: false,
+        render_target: None,
     };
+    let main_render_pipeline: AFRenderPipeline =
+        AFRenderPipeline::new(&context, &main_render_pipeline_config)?;
+
+    let triangle: [Vertex; 3] = [
+        Vertex {
+            position: [0.0, -0.5],
+        },
+        Vertex {
+            position: [0.5, 0.5],
+        },
+        Vertex {
+            position: [-0.5, 0.5],
+        },
+    ];
+    let triangle_buffer: AFBuffer = AFBuffer::new(
+        &context,
+        &AFBufferConfig {
+            data: triangle.as_bytes(),
+            usage: AFBufferUsage {
+                vertex: true,
+                index: None,
+                uniform: false,
+                storage: false,
+            },
+        },
+    )?;
 
     AFContext::mainloop(
         context,
-        move |state| {
-            //
+        move |context, state| {
+            main_render_pipeline
+                .write_uniform_value(context, 0, 0, &0.5f32)
+                .expect("Could not write the uniform.");
+
+            let clear_command: AFRenderCommand = AFRenderCommand::new(
+                &main_render_pipeline,
+                &AFRenderCommandConfig {
+                    colour: [0.1, 0.1, 0.1, 1.0],
+                    clear_depth: 1.0,
+                    clear_stencil: 0,
+                    vertex_count: 0,
+                    enabled_uniform_groups: 0..0,
+                    calls: 1,
+                    command_type: AFRenderCommandType::Empty,
+                    dynamic_slots: Vec::new(),
+                    blend_colour: [0.0, 0.0, 0.0, 0.0],
+                    viewport: None,
+                    scissor_rect: None,
+                    stencil_reference: 0,
+                },
+            )
+            .expect("Could not create the render command.");
+            let triangle_command: AFRenderCommand = AFRenderCommand::new(
+                &main_render_pipeline,
+                &AFRenderCommandConfig {
+                    colour: [0.1, 0.1, 0.1, 1.0],
+                    clear_depth: 1.0,
+                    clear_stencil: 0,
+                    vertex_count: 3,
+                    enabled_uniform_groups: 0..1,
+                    calls: 1,
+                    command_type: AFRenderCommandType::Vertex {
+                        vertex_buffers: vec![triangle_buffer.clone()],
+                    },
+                    dynamic_slots: Vec::new(),
+                    blend_colour: [0.0, 0.0, 0.0, 0.0],
+                    viewport: None,
+                    scissor_rect: None,
+                    stencil_reference: 0,
+                },
+            )
+            .expect("Could not create the render command.");
+
+            if let Some(error) = &state.draw_error {
+                println!("the last frame couldn't be drawn: {}", error);
+            }
+
+            AFMainloopResult {
+                window_commands: match state.close_requested {
+                    true => vec![AFWindowCommand::DestroyWindow],
+                    false => vec![],
+                },
+                render_commands: vec![clear_command, triangle_command],
+            }
         },
         move || {
             println!("finished");
         },
     );
+
+    Ok(())
 }
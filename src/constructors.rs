@@ -1,22 +1,46 @@
 use crate::enums::*;
 use crate::generic::*;
 
-pub trait AFWindowConstructor {
-    fn new(config: &AFWindowConfig) -> Self;
+pub trait AFWindowConstructor: Sized {
+    fn new(config: &AFWindowConfig) -> Result<Self, AFError>;
 }
 
-pub trait AFContextConstructor<Window> {
-    fn new(window: Window, config: &AFContextConfig) -> Self;
+pub trait AFContextConstructor<Window>: Sized {
+    fn new(window: Window, config: &AFContextConfig) -> Result<Self, AFError>;
 }
 
-pub trait AFShaderConstructor<Context> {
-    fn new(context: &Context, config: &AFShaderConfig) -> Self;
+pub trait AFShaderConstructor<Context>: Sized {
+    fn new(context: &Context, config: &AFShaderConfig) -> Result<Self, AFError>;
 }
 
-pub trait AFRenderPipelineConstructor<Context> {
-    fn new(context: &Context, config: &AFRenderPipelineConfig) -> Self;
+pub trait AFRenderPipelineConstructor<Context, Shader>: Sized {
+    fn new(context: &Context, config: &AFRenderPipelineConfig<Shader>) -> Result<Self, AFError>;
 }
 
-pub trait AFRenderCommandConstructor<Pipeline> {
-    fn new(pipeline: &Pipeline, config: &AFRenderCommandConfig) -> Self;
+pub trait AFRenderCommandConstructor<Pipeline, Buffer>: Sized {
+    fn new(pipeline: &Pipeline, config: &AFRenderCommandConfig<Buffer>) -> Result<Self, AFError>;
+}
+
+pub trait AFComputePipelineConstructor<Context, Shader>: Sized {
+    fn new(context: &Context, config: &AFComputePipelineConfig<Shader>) -> Result<Self, AFError>;
+}
+
+pub trait AFComputeCommandConstructor<Pipeline>: Sized {
+    fn new(pipeline: &Pipeline, config: &AFComputeCommandConfig) -> Result<Self, AFError>;
+}
+
+pub trait AFTextureConstructor<Context>: Sized {
+    fn new(context: &Context, config: &AFTextureConfig) -> Result<Self, AFError>;
+}
+
+pub trait AFSamplerConstructor<Context>: Sized {
+    fn new(context: &Context, config: &AFSamplerConfig) -> Result<Self, AFError>;
+}
+
+pub trait AFBufferConstructor<Context>: Sized {
+    fn new(context: &Context, config: &AFBufferConfig) -> Result<Self, AFError>;
+}
+
+pub trait AFRenderTargetConstructor<Context>: Sized {
+    fn new(context: &Context, config: &AFRenderTargetConfig) -> Result<Self, AFError>;
 }
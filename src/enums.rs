@@ -1,5 +1,9 @@
 use crate::util_structs::*;
 
+use std::fmt;
+
+pub type AFKey = winit::event::VirtualKeyCode;
+
 #[derive(Copy, Clone)]
 pub enum AFBackendLibrary {
     Vulkan,
@@ -16,7 +20,7 @@ pub enum AFPowerPreference {
     HighPower,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AFShaderStage {
     None,
     Vertex,
@@ -24,14 +28,83 @@ pub enum AFShaderStage {
     Compute,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AFTextureFormat {
+    R8Unorm,
+    Rg8Unorm,
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Bgra8Unorm,
+    Bgra8UnormSrgb,
+    Rgb10a2Unorm,
+    R16Float,
+    Rg16Float,
+    Rgba16Float,
+    R32Float,
+    Rg32Float,
+    Rgba32Float,
+    R32UnsignedInt,
+    R32Int,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AFDepthFormat {
+    Depth32Float,
+    Depth24Plus,
+    Depth24PlusStencil8,
+}
+
+#[derive(Copy, Clone)]
+pub enum AFCompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+#[derive(Copy, Clone)]
+pub enum AFStencilOperation {
+    Keep,
+    Zero,
+    Replace,
+    Invert,
+    IncrementClamp,
+    DecrementClamp,
+    IncrementWrap,
+    DecrementWrap,
+}
+
 #[derive(Copy, Clone)]
 pub enum AFVertexStepMode {
     PerVertex,
     PerInstance,
 }
 
-#[derive(Copy, Clone)]
+// normalized formats are read as floats between 0 and 1, or -1 and 1 if signed
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AFVertexFormat {
+    UnsignedChar2,
+    UnsignedChar4,
+    Char2,
+    Char4,
+    UnsignedChar2Normalized,
+    UnsignedChar4Normalized,
+    Char2Normalized,
+    Char4Normalized,
+    UnsignedShort2,
+    UnsignedShort4,
+    Short2,
+    Short4,
+    UnsignedShort2Normalized,
+    UnsignedShort4Normalized,
+    Short2Normalized,
+    Short4Normalized,
+    Half2,
+    Half4,
     Float,
     Float2,
     Float3,
@@ -46,14 +119,124 @@ pub enum AFVertexFormat {
     Int4,
 }
 
-#[derive(Copy, Clone)]
+impl AFVertexFormat {
+    // in bytes
+    pub const fn size(&self) -> u32 {
+        return match self {
+            AFVertexFormat::UnsignedChar2
+            | AFVertexFormat::Char2
+            | AFVertexFormat::UnsignedChar2Normalized
+            | AFVertexFormat::Char2Normalized => 2,
+            AFVertexFormat::UnsignedChar4
+            | AFVertexFormat::Char4
+            | AFVertexFormat::UnsignedChar4Normalized
+            | AFVertexFormat::Char4Normalized
+            | AFVertexFormat::UnsignedShort2
+            | AFVertexFormat::Short2
+            | AFVertexFormat::UnsignedShort2Normalized
+            | AFVertexFormat::Short2Normalized
+            | AFVertexFormat::Half2
+            | AFVertexFormat::Float
+            | AFVertexFormat::UnsignedInt
+            | AFVertexFormat::Int => 4,
+            AFVertexFormat::UnsignedShort4
+            | AFVertexFormat::Short4
+            | AFVertexFormat::UnsignedShort4Normalized
+            | AFVertexFormat::Short4Normalized
+            | AFVertexFormat::Half4
+            | AFVertexFormat::Float2
+            | AFVertexFormat::UnsignedInt2
+            | AFVertexFormat::Int2 => 8,
+            AFVertexFormat::Float3 | AFVertexFormat::UnsignedInt3 | AFVertexFormat::Int3 => 12,
+            AFVertexFormat::Float4 | AFVertexFormat::UnsignedInt4 | AFVertexFormat::Int4 => 16,
+        };
+    }
+
+    // the type a shader reads each component as
+    pub fn scalar_type(&self) -> AFShaderScalarType {
+        return match self {
+            AFVertexFormat::UnsignedChar2
+            | AFVertexFormat::UnsignedChar4
+            | AFVertexFormat::UnsignedShort2
+            | AFVertexFormat::UnsignedShort4
+            | AFVertexFormat::UnsignedInt
+            | AFVertexFormat::UnsignedInt2
+            | AFVertexFormat::UnsignedInt3
+            | AFVertexFormat::UnsignedInt4 => AFShaderScalarType::UnsignedInt,
+            AFVertexFormat::Char2
+            | AFVertexFormat::Char4
+            | AFVertexFormat::Short2
+            | AFVertexFormat::Short4
+            | AFVertexFormat::Int
+            | AFVertexFormat::Int2
+            | AFVertexFormat::Int3
+            | AFVertexFormat::Int4 => AFShaderScalarType::Int,
+            _ => AFShaderScalarType::Float,
+        };
+    }
+
+    pub fn components(&self) -> u32 {
+        return match self {
+            AFVertexFormat::Float | AFVertexFormat::UnsignedInt | AFVertexFormat::Int => 1,
+            AFVertexFormat::Float3 | AFVertexFormat::UnsignedInt3 | AFVertexFormat::Int3 => 3,
+            AFVertexFormat::UnsignedChar4
+            | AFVertexFormat::Char4
+            | AFVertexFormat::UnsignedChar4Normalized
+            | AFVertexFormat::Char4Normalized
+            | AFVertexFormat::UnsignedShort4
+            | AFVertexFormat::Short4
+            | AFVertexFormat::UnsignedShort4Normalized
+            | AFVertexFormat::Short4Normalized
+            | AFVertexFormat::Half4
+            | AFVertexFormat::Float4
+            | AFVertexFormat::UnsignedInt4
+            | AFVertexFormat::Int4 => 4,
+            _ => 2,
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AFShaderScalarType {
+    Float,
+    Int,
+    UnsignedInt,
+    Bool,
+}
+
+// what a shader declares at a binding, as found by reflecting it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AFShaderBindingType {
+    UniformBuffer,
+    StorageBuffer,
+    Sampler,
+    Texture,
+    StorageTexture,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AFUniformType {
     Buffer,
     Sampler,
     Storage,
+    ReadOnlyStorage,
+    Texture,
+}
+
+#[derive(Copy, Clone)]
+pub enum AFFilterMode {
+    Nearest,
+    Linear,
 }
 
 #[derive(Copy, Clone)]
+pub enum AFAddressMode {
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AFIndexFormat {
     UnsignedInt16,
     UnsignedInt32,
@@ -106,17 +289,39 @@ pub enum AFFace {
     Back,
 }
 
-pub enum AFRenderCommandType {
+// raw bytes are read in the index buffer's format
+#[derive(Clone)]
+pub enum AFIndexData {
+    UnsignedInt16(Vec<u16>),
+    UnsignedInt32(Vec<u32>),
+    Bytes(Vec<u8>),
+}
+
+impl From<&[u16]> for AFIndexData {
+    fn from(indices: &[u16]) -> Self {
+        return AFIndexData::UnsignedInt16(indices.to_vec());
+    }
+}
+
+impl From<&[u32]> for AFIndexData {
+    fn from(indices: &[u32]) -> Self {
+        return AFIndexData::UnsignedInt32(indices.to_vec());
+    }
+}
+
+#[derive(Clone)]
+pub enum AFRenderCommandType<Buffer> {
     Empty,
     Vertex {
-        vertex_data: Vec<Vec<u8>>,
+        vertex_buffers: Vec<Buffer>,
     },
     Indices {
-        vertex_data: Vec<Vec<u8>>,
-        index_data: Vec<u8>,
+        vertex_buffers: Vec<Buffer>,
+        index_buffer: Buffer,
     },
 }
 
+#[derive(Copy, Clone)]
 pub enum AFWindowCommand {
     UpdateSurface,
     ResizeWindow(AFSize2D<u32>),
@@ -130,3 +335,355 @@ pub enum AFWindowSize {
     Size(AFSize2D<u32>),
 
 }
+
+// a missing graphics adapter isn't one of these, as wgpu 0.4 aborts the
+// process when creating a context finds none for the chosen backend
+#[derive(Clone, Debug)]
+pub enum AFError {
+    InvalidSpirv(String),
+    WindowCreation(String),
+    BadMonitorIndex(usize),
+    ShaderStageMismatch {
+        slot: AFShaderStage,
+        stage: AFShaderStage,
+    },
+    UnreadableFrameFormat(AFTextureFormat),
+    FrameReadback,
+    ImageSizeMismatch {
+        width: u32,
+        height: u32,
+        length: usize,
+    },
+    EmptyImage,
+    Png(String),
+    Io(String),
+    UnsupportedTextureFormat(AFTextureFormat),
+    AnisotropyNotEnabled,
+    NoSuchUniform {
+        set: u32,
+        location: u32,
+        uniform_type: AFUniformType,
+    },
+    UniformSizeMismatch {
+        set: u32,
+        location: u32,
+        byte_size: u32,
+        length: usize,
+    },
+    UniformSlotOutOfRange {
+        set: u32,
+        location: u32,
+        slot: u32,
+        slots: u32,
+    },
+    UniformGroupsOutOfRange {
+        enabled: std::ops::Range<u32>,
+        groups: u32,
+    },
+    BufferReadback,
+    NoDepthTarget,
+    InvalidSampleCount(u32),
+    TargetMismatch,
+    ColourStateMismatch,
+    IndexFormatMismatch {
+        pipeline: AFIndexFormat,
+        data: AFIndexFormat,
+    },
+    MisalignedIndexData {
+        index_format: AFIndexFormat,
+        length: usize,
+    },
+    NotEnoughIndices {
+        vertex_count: u32,
+        indices: usize,
+    },
+    IndexOutOfRange {
+        index: u32,
+        vertices: u64,
+    },
+    VertexBufferCountMismatch {
+        slots: usize,
+        buffers: usize,
+    },
+    MissingBufferUsage(&'static str),
+    BufferWriteOutOfRange {
+        offset: u64,
+        length: usize,
+        byte_size: u64,
+    },
+    MisalignedBufferWrite {
+        offset: u64,
+        length: usize,
+    },
+    VertexAttribOutOfRange {
+        location: u32,
+        offset: u32,
+        size: u32,
+        stride: u64,
+    },
+    NoEntryPoint(String),
+    EntryPointStageMismatch {
+        expected: AFShaderStage,
+        stage: AFShaderStage,
+    },
+    MissingVertexAttrib {
+        location: u32,
+    },
+    VertexAttribMismatch {
+        location: u32,
+        vertex_format: AFVertexFormat,
+        scalar_type: Option<AFShaderScalarType>,
+    },
+    MissingUniform {
+        set: u32,
+        location: u32,
+        stage: AFShaderStage,
+    },
+    UniformTypeMismatch {
+        set: u32,
+        location: u32,
+        uniform_type: AFUniformType,
+        binding_type: AFShaderBindingType,
+    },
+    UniformNotVisible {
+        set: u32,
+        location: u32,
+        stage: AFShaderStage,
+        visible: AFShaderStage,
+    },
+    UniformBlockSizeMismatch {
+        set: u32,
+        location: u32,
+        byte_size: u32,
+        block_size: u64,
+    },
+}
+
+impl fmt::Display for AFError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            AFError::InvalidSpirv(reason) => write!(f, "Invalid SPIR-V bytecode: {}", reason),
+            AFError::WindowCreation(reason) => {
+                write!(f, "The window could not be created: {}", reason)
+            }
+            AFError::BadMonitorIndex(index) => {
+                write!(f, "There is no monitor with the index {}.", index)
+            }
+            AFError::ShaderStageMismatch { slot, stage } => write!(
+                f,
+                "A shader module with stage {:?} was given for the {:?} stage of a pipeline.",
+                stage, slot
+            ),
+            AFError::UnreadableFrameFormat(format) => write!(
+                f,
+                "Frames in the {:?} format cannot be read back as RGBA8.",
+                format
+            ),
+            AFError::FrameReadback => write!(f, "The frame could not be read back from the GPU."),
+            AFError::ImageSizeMismatch {
+                width,
+                height,
+                length,
+            } => write!(
+                f,
+                "A {}x{} RGBA image needs {} bytes, but {} were given.",
+                width,
+                height,
+                *width as usize * *height as usize * 4,
+                length
+            ),
+            AFError::EmptyImage => write!(f, "A texture can't be made from an empty image."),
+            AFError::Png(reason) => write!(f, "Invalid PNG image: {}", reason),
+            AFError::Io(reason) => write!(f, "The file could not be accessed: {}", reason),
+            AFError::UnsupportedTextureFormat(format) => write!(
+                f,
+                "Textures can't be created from RGBA8 images in the {:?} format.",
+                format
+            ),
+            AFError::AnisotropyNotEnabled => write!(
+                f,
+                "An anisotropic sampler needs anisotropic filtering enabled on the context."
+            ),
+            AFError::NoSuchUniform {
+                set,
+                location,
+                uniform_type,
+            } => write!(
+                f,
+                "The pipeline has no {:?} uniform at set {} location {}.",
+                uniform_type, set, location
+            ),
+            AFError::UniformSizeMismatch {
+                set,
+                location,
+                byte_size,
+                length,
+            } => write!(
+                f,
+                "The uniform at set {} location {} is {} bytes, but {} were written.",
+                set, location, byte_size, length
+            ),
+            AFError::UniformSlotOutOfRange {
+                set,
+                location,
+                slot,
+                slots,
+            } => write!(
+                f,
+                "The uniform at set {} location {} has {} slots, so there is no slot {}.",
+                set, location, slots, slot
+            ),
+            AFError::UniformGroupsOutOfRange { enabled, groups } => write!(
+                f,
+                "The uniform groups {:?} were enabled, but the pipeline only has {}.",
+                enabled, groups
+            ),
+            AFError::BufferReadback => write!(f, "The buffer could not be read back from the GPU."),
+            AFError::NoDepthTarget => write!(
+                f,
+                "A pipeline with a depth or stencil test needs a depth texture to draw into."
+            ),
+            AFError::InvalidSampleCount(count) => write!(
+                f,
+                "{} samples per pixel were asked for, but it must be 1, 2, 4, 8, 16 or 32.",
+                count
+            ),
+            AFError::TargetMismatch => write!(
+                f,
+                "A render command's pipeline was made for a different target than it draws into."
+            ),
+            AFError::ColourStateMismatch => write!(
+                f,
+                "A pipeline needs one colour state per attachment of its target, in the same formats."
+            ),
+            AFError::IndexFormatMismatch { pipeline, data } => write!(
+                f,
+                "The pipeline reads {:?} indices, but {:?} indices were given.",
+                pipeline, data
+            ),
+            AFError::MisalignedIndexData {
+                index_format,
+                length,
+            } => write!(
+                f,
+                "{} bytes of index data can't be split into {:?} indices.",
+                length, index_format
+            ),
+            AFError::NotEnoughIndices {
+                vertex_count,
+                indices,
+            } => write!(
+                f,
+                "{} vertices are drawn, but only {} indices were given.",
+                vertex_count, indices
+            ),
+            AFError::IndexOutOfRange { index, vertices } => write!(
+                f,
+                "The index {} is past the {} vertices in the vertex buffers.",
+                index, vertices
+            ),
+            AFError::VertexBufferCountMismatch { slots, buffers } => write!(
+                f,
+                "The pipeline has {} vertex buffer slots, but {} vertex buffers were given.",
+                slots, buffers
+            ),
+            AFError::MissingBufferUsage(usage) => {
+                write!(f, "The buffer wasn't made with the {} usage.", usage)
+            }
+            AFError::BufferWriteOutOfRange {
+                offset,
+                length,
+                byte_size,
+            } => write!(
+                f,
+                "{} bytes written at offset {} don't fit in a {} byte buffer.",
+                length, offset, byte_size
+            ),
+            AFError::MisalignedBufferWrite { offset, length } => write!(
+                f,
+                "Buffer writes must be aligned to 4 bytes, but {} bytes were written at offset {}.",
+                length, offset
+            ),
+            AFError::VertexAttribOutOfRange {
+                location,
+                offset,
+                size,
+                stride,
+            } => write!(
+                f,
+                "The {} byte vertex attribute at location {} and offset {} doesn't fit in a stride of {}.",
+                size, location, offset, stride
+            ),
+            AFError::NoEntryPoint(entry_point) => {
+                write!(f, "The shader has no entry point named {}.", entry_point)
+            }
+            AFError::EntryPointStageMismatch { expected, stage } => write!(
+                f,
+                "The shader was given as a {:?} shader, but its entry point is a {:?} shader.",
+                expected, stage
+            ),
+            AFError::MissingVertexAttrib { location } => write!(
+                f,
+                "The vertex shader reads an input at location {}, but no vertex buffer slot has an attribute there.",
+                location
+            ),
+            AFError::VertexAttribMismatch {
+                location,
+                vertex_format,
+                scalar_type,
+            } => match scalar_type {
+                Some(scalar_type) => write!(
+                    f,
+                    "The vertex attribute at location {} is {:?}, but the shader reads {:?} values there.",
+                    location, vertex_format, scalar_type
+                ),
+                None => write!(
+                    f,
+                    "The vertex attribute at location {} is {:?}, but the shader's input there isn't a scalar or a vector.",
+                    location, vertex_format
+                ),
+            },
+            AFError::MissingUniform {
+                set,
+                location,
+                stage,
+            } => write!(
+                f,
+                "The {:?} shader uses a binding at set {} location {}, but the pipeline has no uniform there.",
+                stage, set, location
+            ),
+            AFError::UniformTypeMismatch {
+                set,
+                location,
+                uniform_type,
+                binding_type,
+            } => write!(
+                f,
+                "The uniform at set {} location {} is a {:?}, but the shader declares a {:?} there.",
+                set, location, uniform_type, binding_type
+            ),
+            AFError::UniformNotVisible {
+                set,
+                location,
+                stage,
+                visible,
+            } => write!(
+                f,
+                "The uniform at set {} location {} is used by the {:?} shader, but is only visible to the {:?} stage.",
+                set, location, stage, visible
+            ),
+            AFError::UniformBlockSizeMismatch {
+                set,
+                location,
+                byte_size,
+                block_size,
+            } => write!(
+                f,
+                "The uniform at set {} location {} is {} bytes, but the shader's block there needs {} bytes.",
+                set, location, byte_size, block_size
+            ),
+        };
+    }
+}
+
+impl std::error::Error for AFError {}
@@ -1,6 +1,11 @@
 use crate::enums::*;
 use crate::util_structs::{AFImage, AFSize2D};
 use std::ops::Range;
+use std::path::PathBuf;
+use zerocopy::AsBytes;
+
+#[cfg(feature = "derive")]
+pub use AlfredGF_rs_derive::AFVertex;
 
 pub struct AFWindowConfig<'a> {
     pub icon: Option<&'a AFImage<'a>>,
@@ -24,6 +29,13 @@ pub struct AFContextConfig {
     pub anisotropic_filtering: bool,
     pub backend_lib: AFBackendLibrary,
     pub power_preference: AFPowerPreference,
+    pub swap_chain_format: AFTextureFormat,
+    // the context keeps a depth texture in this format the size of its frames
+    pub depth_format: Option<AFDepthFormat>,
+    // above 1, frames are drawn multisampled and resolved
+    pub sample_count: u32,
+    // the size of the staging buffers that buffer writes are packed into
+    pub upload_chunk_size: u64,
 }
 
 #[derive(Copy, Clone)]
@@ -47,6 +59,60 @@ pub struct AFVertexBuffer<'a> {
     pub attribs: &'a [AFVertexAttrib],
 }
 
+// a #[repr(C)] struct whose fields are the attributes of a vertex,
+// usually implemented with #[derive(AFVertex)] from AlfredGF-rs-derive
+pub trait AFVertex: AsBytes {
+    const STRIDE: u64;
+    const ATTRIBS: &'static [AFVertexAttrib];
+
+    fn vertex_buffer(step_mode: AFVertexStepMode) -> AFVertexBuffer<'static> {
+        return AFVertexBuffer {
+            stride: Self::STRIDE,
+            step_mode,
+            attribs: Self::ATTRIBS,
+        };
+    }
+}
+
+// the vertex format a field of this type is read as; normalized and
+// half formats have to be given with #[vertex_format(...)] instead
+pub trait AFVertexAttribType {
+    const VERTEX_FORMAT: AFVertexFormat;
+}
+
+macro_rules! vertex_attrib_types {
+    ($($attrib_type:ty => $vertex_format:ident,)*) => {
+        $(
+            impl AFVertexAttribType for $attrib_type {
+                const VERTEX_FORMAT: AFVertexFormat = AFVertexFormat::$vertex_format;
+            }
+        )*
+    };
+}
+
+vertex_attrib_types! {
+    f32 => Float,
+    [f32; 2] => Float2,
+    [f32; 3] => Float3,
+    [f32; 4] => Float4,
+    u32 => UnsignedInt,
+    [u32; 2] => UnsignedInt2,
+    [u32; 3] => UnsignedInt3,
+    [u32; 4] => UnsignedInt4,
+    i32 => Int,
+    [i32; 2] => Int2,
+    [i32; 3] => Int3,
+    [i32; 4] => Int4,
+    [u8; 2] => UnsignedChar2,
+    [u8; 4] => UnsignedChar4,
+    [i8; 2] => Char2,
+    [i8; 4] => Char4,
+    [u16; 2] => UnsignedShort2,
+    [u16; 4] => UnsignedShort4,
+    [i16; 2] => Short2,
+    [i16; 4] => Short4,
+}
+
 #[derive(Copy, Clone)]
 pub struct AFUniform {
     pub location: u32,
@@ -54,6 +120,9 @@ pub struct AFUniform {
     pub dynamic: bool,
     pub byte_size: u32,
     pub uniform_type: AFUniformType,
+    // how many copies of a dynamic uniform its buffer holds,
+    // each draw picking one; ignored if it isn't dynamic
+    pub slots: u32,
 }
 
 #[derive(Copy, Clone)]
@@ -62,6 +131,44 @@ pub struct AFUniformGroup<'a> {
     pub uniforms: &'a [AFUniform],
 }
 
+pub struct AFTextureConfig<'a> {
+    pub image: AFImage<'a>,
+    pub format: AFTextureFormat,
+    // 0 generates the full chain down to 1x1
+    pub mip_levels: u32,
+}
+
+#[derive(Copy, Clone)]
+pub struct AFSamplerConfig {
+    pub mag_filter: AFFilterMode,
+    pub min_filter: AFFilterMode,
+    pub mipmap_filter: AFFilterMode,
+    pub address_mode_u: AFAddressMode,
+    pub address_mode_v: AFAddressMode,
+    pub address_mode_w: AFAddressMode,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+    // needs anisotropic filtering enabled on the context; wgpu 0.4 can't set a
+    // sampler's anisotropy, so for now this only overrides all three filters with Linear
+    pub anisotropic: bool,
+}
+
+// what a buffer can be used for, any number at once
+#[derive(Copy, Clone)]
+pub struct AFBufferUsage {
+    pub vertex: bool,
+    // the format its indices are in, if it's an index buffer
+    pub index: Option<AFIndexFormat>,
+    pub uniform: bool,
+    pub storage: bool,
+}
+
+pub struct AFBufferConfig<'a> {
+    // the initial contents, which also give the buffer its size
+    pub data: &'a [u8],
+    pub usage: AFBufferUsage,
+}
+
 #[derive(Copy, Clone)]
 pub struct AFBlendDescriptor {
     pub src_factor: AFBlendFactor,
@@ -69,24 +176,131 @@ pub struct AFBlendDescriptor {
     pub operation: AFBlendOperation,
 }
 
+// in pixels from the top left, with depths between 0 and 1
 #[derive(Copy, Clone)]
-pub struct AFRenderPipelineConfig<'a> {
-    pub uniforms: &'a [AFUniformGroup<'a>],
-    pub vertex_buffer_slots: &'a [AFVertexBuffer<'a>],
+pub struct AFViewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
+// in pixels from the top left; nothing outside of it is drawn
+#[derive(Copy, Clone)]
+pub struct AFScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Copy, Clone)]
+pub struct AFColourWriteMask {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub alpha: bool,
+}
+
+impl AFColourWriteMask {
+    pub const ALL: AFColourWriteMask = AFColourWriteMask {
+        red: true,
+        green: true,
+        blue: true,
+        alpha: true,
+    };
+}
+
+// how a pipeline writes to one colour attachment
+#[derive(Copy, Clone)]
+pub struct AFColourState {
+    pub format: AFTextureFormat,
     pub colour_blend: AFBlendDescriptor,
     pub alpha_blend: AFBlendDescriptor,
+    pub write_mask: AFColourWriteMask,
+}
+
+#[derive(Copy, Clone)]
+pub struct AFRenderTargetConfig<'a> {
+    pub size: AFSize2D<u32>,
+    // one colour attachment per format
+    pub formats: &'a [AFTextureFormat],
+    pub depth_format: Option<AFDepthFormat>,
+}
+
+#[derive(Copy, Clone)]
+pub struct AFStencilFaceDescriptor {
+    pub compare: AFCompareFunction,
+    pub fail_op: AFStencilOperation,
+    pub depth_fail_op: AFStencilOperation,
+    pub pass_op: AFStencilOperation,
+}
+
+#[derive(Copy, Clone)]
+pub struct AFDepthStencilDescriptor {
+    pub depth_write: bool,
+    pub depth_compare: AFCompareFunction,
+    pub stencil_front: AFStencilFaceDescriptor,
+    pub stencil_back: AFStencilFaceDescriptor,
+    pub stencil_read_mask: u32,
+    pub stencil_write_mask: u32,
+}
+
+pub struct AFRenderPipelineConfig<'a, Shader> {
+    pub vertex_shader: &'a Shader,
+    pub fragment_shader: Option<&'a Shader>,
+    pub uniforms: &'a [AFUniformGroup<'a>],
+    pub vertex_buffer_slots: &'a [AFVertexBuffer<'a>],
+    // one per colour attachment of the frame or render target, in order
+    pub colour_states: &'a [AFColourState],
     pub primitive: AFDrawablePrimitive,
     pub front_face: AFDirection,
     pub cull_mode: Option<AFFace>,
     pub index_format: AFIndexFormat,
+    // tested against the context's depth texture
+    pub depth_stencil: Option<AFDepthStencilDescriptor>,
+    // which of the context's samples are written
+    pub sample_mask: u32,
+    pub alpha_to_coverage: bool,
+    // the kind of render target the pipeline draws into; the context's frames if None
+    pub render_target: Option<&'a AFRenderTargetConfig<'a>>,
+}
+
+pub struct AFRenderCommandConfig<Buffer> {
+    pub colour: [f64; 4],
+    // like the colour, only used by the first command of a frame
+    pub clear_depth: f32,
+    pub clear_stencil: u32,
+    pub vertex_count: u32,
+    pub enabled_uniform_groups: Range<u32>,
+    pub calls: u32,
+    pub command_type: AFRenderCommandType<Buffer>,
+    // indexed by set, the slot of each dynamic uniform in location order;
+    // sets which aren't given use the first slot of each
+    pub dynamic_slots: Vec<Vec<u32>>,
+    // used by the AFBlendFactor::BlendColour factors
+    pub blend_colour: [f64; 4],
+    // the whole target if None
+    pub viewport: Option<AFViewport>,
+    pub scissor_rect: Option<AFScissorRect>,
+    // compared against by the pipeline's stencil test
+    pub stencil_reference: u32,
 }
 
-pub struct AFRenderCommandConfig {
-    colour: [f64; 4],
-    vertex_count: u32,
-    enabled_uniform_groups: Range<u32>,
-    calls: u32,
-    command_type: AFRenderCommandType,
+pub struct AFComputePipelineConfig<'a, Shader> {
+    pub compute_shader: &'a Shader,
+    pub uniforms: &'a [AFUniformGroup<'a>],
+}
+
+pub struct AFComputeCommandConfig {
+    // along x, y and z
+    pub workgroups: [u32; 3],
+    pub enabled_uniform_groups: Range<u32>,
+    // indexed by set, the slot of each dynamic uniform in location order;
+    // sets which aren't given use the first slot of each
+    pub dynamic_slots: Vec<Vec<u32>>,
 }
 
 pub struct AFMonitor {
@@ -98,14 +312,34 @@ pub struct AFMonitor {
 
 }
 
-#[derive(Copy, Clone)]
+// the state of the window when a frame is drawn; things that
+// only happen once (eg. was_resized, dropped_files, just_pressed_keys)
+// are cleared after every frame
+#[derive(Clone)]
 pub struct AFMainloopState {
-    //
+    pub size: AFSize2D<u32>,
+    pub focused: bool,
+    pub close_requested: bool,
+    pub was_resized: bool,
+    pub hovered_files: Vec<PathBuf>,
+    pub dropped_files: Vec<PathBuf>,
+    pub pressed_keys: Vec<AFKey>,
+    pub just_pressed_keys: Vec<AFKey>,
+    pub cursor_position: AFSize2D<i32>,
+    // why the previous frame's render commands couldn't be drawn, if they couldn't
+    pub draw_error: Option<AFError>,
+}
+
+// returned by the mainloop closure every frame; the window
+// commands are applied before the render commands are drawn
+pub struct AFMainloopResult<RenderCommand> {
+    pub window_commands: Vec<AFWindowCommand>,
+    pub render_commands: Vec<RenderCommand>,
 }
 
-pub trait AFMainloop {
-    fn mainloop<F: 'static, T: 'static>(context: Self, on_loop: F, on_finish: T)
+pub trait AFMainloop<RenderCommand> {
+    fn mainloop<F, T>(context: Self, on_loop: F, on_finish: T)
     where
-        F: Fn(AFMainloopState) -> (),
-        T: Fn() -> ();
+        F: 'static + Fn(&Self, &AFMainloopState) -> AFMainloopResult<RenderCommand>,
+        T: 'static + Fn();
 }
@@ -1,18 +1,38 @@
 use crate::constructors::*;
 use crate::enums::*;
 use crate::generic::*;
+use crate::reflection::*;
 use crate::util_structs::*;
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use zerocopy::AsBytes;
+
 use wgpu::{
-    read_spirv, Adapter, BackendBit, BlendDescriptor, Device, DeviceDescriptor, Extensions, Limits,
-    PowerPreference, PresentMode, Queue, RequestAdapterOptions, ShaderModule, ShaderStage,
+    read_spirv, Adapter, AddressMode, BackendBit, BindGroup, BindGroupDescriptor, BindGroupLayout,
+    BindGroupLayoutBinding, BindGroupLayoutDescriptor, Binding, BindingResource, BindingType,
+    BlendDescriptor, BlendFactor, BlendOperation, Buffer, BufferCopyView, BufferDescriptor,
+    BufferMapAsyncResult, BufferUsage, Color, ColorStateDescriptor, ColorWrite, CommandEncoder,
+    CommandEncoderDescriptor, CompareFunction, ComputePipeline, ComputePipelineDescriptor, CullMode,
+    DepthStencilStateDescriptor, Device, DeviceDescriptor, Extensions, Extent3d, FilterMode,
+    FrontFace, IndexFormat, InputStepMode, Limits, LoadOp, Origin3d, PipelineLayout,
+    PipelineLayoutDescriptor, PowerPreference, PresentMode, PrimitiveTopology,
+    ProgrammableStageDescriptor, Queue, RasterizationStateDescriptor,
+    RenderPassColorAttachmentDescriptor, RenderPassDepthStencilAttachmentDescriptor,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions, Sampler,
+    SamplerDescriptor, ShaderModule, ShaderStage, StencilOperation, StencilStateFaceDescriptor,
+    StoreOp, Surface, SwapChain, SwapChainDescriptor, Texture, TextureCopyView, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsage, TextureView, TextureViewDimension,
+    VertexAttributeDescriptor, VertexBufferDescriptor, VertexFormat,
 };
 use winit::{
     dpi::PhysicalSize,
     event_loop::{EventLoop, ControlFlow},
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, WindowEvent},
     window::{Icon, Window, WindowBuilder, Fullscreen},
-    monitor::{MonitorHandle,},
 };
 
 pub struct AFWindow {
@@ -20,29 +40,190 @@ pub struct AFWindow {
     event_loop: EventLoop<()>,
 }
 
+// where the frames of a context are drawn to
+enum AFFrameTarget {
+    Window {
+        window: Window,
+        surface: Surface,
+        swap_chain: SwapChain,
+        // kept so that the frame can be drawn again to be read back
+        last_commands: Vec<AFRenderCommand>,
+    },
+    Offscreen {
+        texture: Texture,
+        view: TextureView,
+    },
+}
+
 pub struct AFContext {
-    window: Window,
-    event_loop: EventLoop<()>,
+    target: AFFrameTarget,
+    event_loop: Option<EventLoop<()>>,
+    frame_format: AFTextureFormat,
+    swap_chain_format: TextureFormat,
+    depth_format: Option<TextureFormat>,
+    depth_target: Option<(Texture, TextureView)>,
+    sample_count: u32,
+    // drawn into instead of the frame when multisampling, then resolved to it
+    multisampled_target: Option<(Texture, TextureView)>,
+    size: AFSize2D<u32>,
     present_mode: PresentMode,
     device: Device,
-    queue: Queue,
+    queue: RefCell<Queue>,
+    anisotropic_filtering: bool,
+    pending_reads: RefCell<Vec<AFPendingRead>>,
+    upload_ring: RefCell<AFUploadRing>,
+    #[allow(dead_code)]
     adapter: Adapter,
 }
 
 pub struct AFShaderModule {
     module: ShaderModule,
-    stage: ShaderStage,
+    stage: AFShaderStage,
     entry: String,
+    reflection: AFShaderReflection,
 }
 
+// the pipeline and its bind groups are shared with the render
+// commands made from it, so the commands never have to borrow the pipeline
 pub struct AFRenderPipeline {
-    //
+    render_pipeline: Rc<RenderPipeline>,
+    pass_format: AFPassFormat,
+    bindings: AFBindings,
+    index_format: AFIndexFormat,
+    // per slot, None if it's stepped per instance
+    vertex_strides: Vec<Option<u64>>,
+}
+
+// the attachments a pipeline was made for, which its commands must draw into
+#[derive(Clone, PartialEq)]
+struct AFPassFormat {
+    colour_formats: Vec<TextureFormat>,
+    depth_format: Option<TextureFormat>,
+    sample_count: u32,
+}
+
+// a texture commands can draw into and which can then be sampled, even in the same frame
+pub struct AFRenderTarget {
+    colours: Vec<AFTexture>,
+    depth_target: Option<(Texture, TextureView)>,
+    pass_format: AFPassFormat,
+}
+
+pub struct AFComputePipeline {
+    compute_pipeline: Rc<ComputePipeline>,
+    bindings: AFBindings,
+}
+
+// the bind groups of a pipeline's uniform groups and the resources behind them
+struct AFBindings {
+    bind_groups: Rc<Vec<BindGroup>>,
+    // kept to rebuild the bind groups when a resource is bound
+    bind_group_layouts: Vec<BindGroupLayout>,
+    uniform_groups: Vec<Vec<AFUniform>>,
+    resources: HashMap<(u32, u32), AFUniformResource>,
+}
+
+// the resource behind a uniform slot, shared so that
+// textures and samplers can be bound to many pipelines
+enum AFUniformResource {
+    Buffer(Rc<Buffer>),
+    Sampler(Rc<Sampler>),
+    // the texture is kept alive for its view
+    Texture(#[allow(dead_code)] Rc<Texture>, Rc<TextureView>),
+}
+
+pub struct AFTexture {
+    texture: Rc<Texture>,
+    view: Rc<TextureView>,
+}
+
+pub struct AFSampler {
+    sampler: Rc<Sampler>,
+}
+
+#[derive(Clone)]
+pub struct AFBuffer {
+    buffer: Rc<Buffer>,
+    byte_size: u64,
+    usage: AFBufferUsage,
+    // a copy of an index buffer's contents, so that
+    // render commands can check the indices they draw
+    indices: Option<Rc<RefCell<Vec<u8>>>>,
+}
+
+// a staging buffer being mapped for a read, kept alive until its callback has run
+struct AFPendingRead {
+    #[allow(dead_code)]
+    buffer: Buffer,
+    done: Rc<Cell<bool>>,
+}
+
+// staging buffers that the writes of a frame are packed into, reused
+// in a ring once the GPU has finished copying out of them
+struct AFUploadRing {
+    chunk_size: u64,
+    chunks: Vec<AFStagingChunk>,
+    // where the search for a free chunk starts
+    cursor: usize,
+    // the writes waiting to be copied in, back to back
+    data: Vec<u8>,
+    copies: Vec<AFStagedCopy>,
+}
+
+struct AFStagingChunk {
+    buffer: Buffer,
+    size: u64,
+    free: Rc<Cell<bool>>,
+}
+
+struct AFStagedCopy {
+    buffer: Rc<Buffer>,
+    offset: u64,
+    data_offset: u64,
+    length: u64,
+}
+
+// the views a render pass draws into
+struct AFAttachments<'a> {
+    // each colour attachment and the view it resolves to, if any
+    colours: Vec<(&'a TextureView, Option<&'a TextureView>)>,
+    depth: Option<&'a TextureView>,
+}
+
+#[derive(Clone)]
+pub struct AFRenderCommand {
+    render_pipeline: Rc<RenderPipeline>,
+    pass_format: AFPassFormat,
+    bind_groups: Rc<Vec<BindGroup>>,
+    colour: [f64; 4],
+    clear_depth: f32,
+    clear_stencil: u32,
+    vertex_count: u32,
+    enabled_uniform_groups: Range<u32>,
+    // per set, in bytes
+    dynamic_offsets: Vec<Vec<u64>>,
+    calls: u32,
+    command_type: AFRenderCommandType<AFBuffer>,
+    blend_colour: [f64; 4],
+    viewport: Option<AFViewport>,
+    scissor_rect: Option<AFScissorRect>,
+    stencil_reference: u32,
+}
+
+#[derive(Clone)]
+pub struct AFComputeCommand {
+    compute_pipeline: Rc<ComputePipeline>,
+    bind_groups: Rc<Vec<BindGroup>>,
+    enabled_uniform_groups: Range<u32>,
+    // per set, in bytes
+    dynamic_offsets: Vec<Vec<u64>>,
+    workgroups: [u32; 3],
 }
 
 // implementation
 
 impl AFWindowConstructor for AFWindow {
-    fn new(config: &AFWindowConfig) -> Self {
+    fn new(config: &AFWindowConfig) -> Result<Self, AFError> {
         let event_loop: EventLoop<()> = EventLoop::new();
         let monitor = match config.monitor_chooser {
             None => {
@@ -68,7 +249,7 @@ impl AFWindowConstructor for AFWindow {
                 let m_n = closure(monitors);
                 let m = event_loop.available_monitors().nth(m_n);
 
-                m.expect("Could not find a suitable monitor.")
+                m.ok_or(AFError::BadMonitorIndex(m_n))?
             },
         };
 
@@ -110,9 +291,13 @@ impl AFWindowConstructor for AFWindow {
             .with_decorations(config.decorated)
             .with_window_icon(match config.icon {
                 Some(icon) => {
-                    Icon::from_rgba(icon.data.to_vec(), icon.size.width, icon.size.height).ok()
+                    icon.validate()?;
+                    Some(
+                        Icon::from_rgba(icon.data.to_vec(), icon.size.width, icon.size.height)
+                            .map_err(|error| AFError::WindowCreation(error.to_string()))?,
+                    )
                 }
-                None => Icon::from_rgba(vec![], 0, 0).ok(),
+                None => None,
             })
             .with_fullscreen(match config.fullscreen {
                 true => {
@@ -126,105 +311,2647 @@ impl AFWindowConstructor for AFWindow {
             .with_always_on_top(config.always_on_top)
             .with_maximized(config.maximized);
 
-        let window = builder.build(&event_loop).unwrap();
+        let window = builder
+            .build(&event_loop)
+            .map_err(|error| AFError::WindowCreation(error.to_string()))?;
 
-        return AFWindow { window, event_loop };
+        return Ok(AFWindow { window, event_loop });
     }
 }
 
 impl AFContextConstructor<AFWindow> for AFContext {
     // absorbs the window
-    fn new(window: AFWindow, config: &AFContextConfig) -> Self {
-        let adapter: Adapter = Adapter::request(&RequestAdapterOptions {
-            power_preference: match config.power_preference {
-                AFPowerPreference::LowPower => PowerPreference::LowPower,
-                AFPowerPreference::Default => PowerPreference::Default,
-                AFPowerPreference::HighPower => PowerPreference::HighPerformance,
-            },
-            backends: match config.backend_lib {
-                AFBackendLibrary::Vulkan => BackendBit::VULKAN,
-                AFBackendLibrary::Metal => BackendBit::METAL,
-                AFBackendLibrary::OpenGL => BackendBit::GL,
-                AFBackendLibrary::DX12 => BackendBit::DX12,
-                AFBackendLibrary::DX11 => BackendBit::DX11,
-            },
-        })
-        .unwrap();
-
-        let (device, queue): (Device, Queue) = adapter.request_device(&DeviceDescriptor {
-            extensions: Extensions {
-                anisotropic_filtering: config.anisotropic_filtering,
-            },
-            limits: Limits::default(),
-        });
+    fn new(window: AFWindow, config: &AFContextConfig) -> Result<Self, AFError> {
+        let surface: Surface = Surface::create(&window.window);
+        let (adapter, device, queue): (Adapter, Device, Queue) = request_device(config);
 
         let present_mode: PresentMode = match config.vsync {
             true => PresentMode::Vsync,
             false => PresentMode::NoVsync,
         };
 
-        return AFContext {
-            window: window.window,
-            event_loop: window.event_loop,
+        let swap_chain_format: TextureFormat = texture_format(config.swap_chain_format);
+        let depth_format: Option<TextureFormat> = config.depth_format.map(depth_format);
+        check_sample_count(config.sample_count)?;
+        let size: AFSize2D<u32> = AFSize2D {
+            width: window.window.inner_size().width,
+            height: window.window.inner_size().height,
+        };
+        let swap_chain: SwapChain = create_swap_chain(
+            &device,
+            &surface,
+            swap_chain_format,
+            size,
+            present_mode,
+        );
+
+        return Ok(AFContext {
+            target: AFFrameTarget::Window {
+                window: window.window,
+                surface,
+                swap_chain,
+                last_commands: Vec::new(),
+            },
+            event_loop: Some(window.event_loop),
+            frame_format: config.swap_chain_format,
+            swap_chain_format,
+            depth_format,
+            depth_target: depth_format
+                .map(|format| create_depth_target(&device, format, size, config.sample_count)),
+            sample_count: config.sample_count,
+            multisampled_target: create_multisampled_target(
+                &device,
+                swap_chain_format,
+                size,
+                config.sample_count,
+            ),
+            size,
             present_mode,
             device,
-            queue,
+            queue: RefCell::new(queue),
+            anisotropic_filtering: config.anisotropic_filtering,
+            pending_reads: RefCell::new(Vec::new()),
+            upload_ring: RefCell::new(AFUploadRing::new(config.upload_chunk_size)),
+            adapter,
+        });
+    }
+}
+
+// a headless context, which draws into an offscreen
+// colour target of the given size instead of a window
+impl AFContextConstructor<AFSize2D<u32>> for AFContext {
+    fn new(size: AFSize2D<u32>, config: &AFContextConfig) -> Result<Self, AFError> {
+        let (adapter, device, queue): (Adapter, Device, Queue) = request_device(config);
+
+        let swap_chain_format: TextureFormat = texture_format(config.swap_chain_format);
+        let depth_format: Option<TextureFormat> = config.depth_format.map(depth_format);
+        check_sample_count(config.sample_count)?;
+        let (texture, view): (Texture, TextureView) =
+            create_offscreen_target(&device, swap_chain_format, size);
+
+        return Ok(AFContext {
+            target: AFFrameTarget::Offscreen { texture, view },
+            event_loop: None,
+            frame_format: config.swap_chain_format,
+            swap_chain_format,
+            depth_format,
+            depth_target: depth_format
+                .map(|format| create_depth_target(&device, format, size, config.sample_count)),
+            sample_count: config.sample_count,
+            multisampled_target: create_multisampled_target(
+                &device,
+                swap_chain_format,
+                size,
+                config.sample_count,
+            ),
+            size,
+            present_mode: match config.vsync {
+                true => PresentMode::Vsync,
+                false => PresentMode::NoVsync,
+            },
+            device,
+            queue: RefCell::new(queue),
+            anisotropic_filtering: config.anisotropic_filtering,
+            pending_reads: RefCell::new(Vec::new()),
+            upload_ring: RefCell::new(AFUploadRing::new(config.upload_chunk_size)),
             adapter,
+        });
+    }
+}
+
+impl AFContext {
+    // recreates the surface and its swap chain from the window
+    pub fn update_surface(&mut self) {
+        if let AFFrameTarget::Window {
+            window, surface, ..
+        } = &mut self.target
+        {
+            *surface = Surface::create(window);
+            let size: PhysicalSize<u32> = window.inner_size();
+            self.resize_frame(AFSize2D {
+                width: size.width,
+                height: size.height,
+            });
+        }
+    }
+
+    // resizes the window, or the offscreen colour target of a headless context
+    pub fn resize_window(&mut self, size: AFSize2D<u32>) {
+        if let AFFrameTarget::Window { window, .. } = &self.target {
+            window.set_inner_size(PhysicalSize::new(size.width, size.height));
+        }
+        self.resize_frame(size);
+    }
+
+    fn resize_frame(&mut self, size: AFSize2D<u32>) {
+        self.size = size;
+
+        // a minimized window has no area to present to, so the
+        // old swap chain is kept until the window comes back
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        match &mut self.target {
+            AFFrameTarget::Window {
+                surface,
+                swap_chain,
+                ..
+            } => {
+                *swap_chain = create_swap_chain(
+                    &self.device,
+                    surface,
+                    self.swap_chain_format,
+                    size,
+                    self.present_mode,
+                );
+            }
+            AFFrameTarget::Offscreen { texture, view } => {
+                let (new_texture, new_view): (Texture, TextureView) =
+                    create_offscreen_target(&self.device, self.swap_chain_format, size);
+                *texture = new_texture;
+                *view = new_view;
+            }
+        }
+
+        self.depth_target = self
+            .depth_format
+            .map(|format| create_depth_target(&self.device, format, size, self.sample_count));
+        self.multisampled_target = create_multisampled_target(
+            &self.device,
+            self.swap_chain_format,
+            size,
+            self.sample_count,
+        );
+    }
+
+    // draws to the next swap chain frame, or to the offscreen target of a headless context
+    pub fn draw(&mut self, commands: &[AFRenderCommand]) -> Result<(), AFError> {
+        check_pass_format(commands, &self.frame_pass_format())?;
+        self.flush_uploads();
+        if self.size.width == 0 || self.size.height == 0 {
+            return Ok(());
+        }
+
+        let mut encoder: CommandEncoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+
+        match &mut self.target {
+            AFFrameTarget::Window {
+                swap_chain,
+                last_commands,
+                ..
+            } => {
+                let frame = swap_chain.get_next_texture();
+                encode_render_commands(
+                    &mut encoder,
+                    &frame_attachments(
+                        &frame.view,
+                        &self.multisampled_target,
+                        &self.depth_target,
+                    ),
+                    commands,
+                );
+                self.queue.borrow_mut().submit(&[encoder.finish()]);
+
+                *last_commands = commands.to_vec();
+            }
+            AFFrameTarget::Offscreen { view, .. } => {
+                encode_render_commands(
+                    &mut encoder,
+                    &frame_attachments(view, &self.multisampled_target, &self.depth_target),
+                    commands,
+                );
+                self.queue.borrow_mut().submit(&[encoder.finish()]);
+            }
+        }
+
+        return Ok(());
+    }
+
+    // draws into a render target straight away, so that it can
+    // be sampled by the commands drawn to the frame afterwards
+    pub fn draw_to(
+        &self,
+        render_target: &AFRenderTarget,
+        commands: &[AFRenderCommand],
+    ) -> Result<(), AFError> {
+        check_pass_format(commands, &render_target.pass_format)?;
+        self.flush_uploads();
+
+        let mut encoder: CommandEncoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+        encode_render_commands(
+            &mut encoder,
+            &AFAttachments {
+                colours: render_target
+                    .colours
+                    .iter()
+                    .map(|colour| (colour.view.as_ref(), None))
+                    .collect::<Vec<_>>(),
+                depth: render_target.depth_target.as_ref().map(|(_, view)| view),
+            },
+            commands,
+        );
+        self.queue.borrow_mut().submit(&[encoder.finish()]);
+
+        return Ok(());
+    }
+
+    fn frame_pass_format(&self) -> AFPassFormat {
+        return AFPassFormat {
+            colour_formats: vec![self.swap_chain_format],
+            depth_format: self.depth_format,
+            sample_count: self.sample_count,
+        };
+    }
+
+    // reads the last frame back as RGBA8; a headless context reads its target as it
+    // was drawn, but the swap chain can't be copied from, so a window's commands are
+    // drawn again into a texture with whatever their buffers and render targets hold now
+    pub fn redraw_frame(&self) -> Result<AFOwnedImage, AFError> {
+        let swap_red_blue: bool = match self.frame_format {
+            AFTextureFormat::Rgba8Unorm | AFTextureFormat::Rgba8UnormSrgb => false,
+            AFTextureFormat::Bgra8Unorm | AFTextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(AFError::UnreadableFrameFormat(format)),
+        };
+
+        if self.size.width == 0 || self.size.height == 0 {
+            return Ok(AFOwnedImage {
+                size: self.size,
+                data: Vec::new(),
+            });
+        }
+
+        let mut encoder: CommandEncoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+
+        let redrawn: (Texture, TextureView);
+        let texture: &Texture = match &self.target {
+            AFFrameTarget::Window { last_commands, .. } => {
+                redrawn = create_offscreen_target(&self.device, self.swap_chain_format, self.size);
+                encode_render_commands(
+                    &mut encoder,
+                    &frame_attachments(
+                        &redrawn.1,
+                        &self.multisampled_target,
+                        &self.depth_target,
+                    ),
+                    last_commands,
+                );
+                &redrawn.0
+            }
+            AFFrameTarget::Offscreen { texture, .. } => texture,
+        };
+
+        // rows of a buffer copy must be aligned to 256 bytes
+        let row_size: u32 = self.size.width * 4;
+        let row_pitch: u32 = row_size.div_ceil(256) * 256;
+        let buffer_size: u64 = row_pitch as u64 * self.size.height as u64;
+
+        let buffer: Buffer = self.device.create_buffer(&BufferDescriptor {
+            size: buffer_size,
+            usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+        });
+        encoder.copy_texture_to_buffer(
+            TextureCopyView {
+                texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: Origin3d {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            },
+            BufferCopyView {
+                buffer: &buffer,
+                offset: 0,
+                row_pitch,
+                image_height: self.size.height,
+            },
+            Extent3d {
+                width: self.size.width,
+                height: self.size.height,
+                depth: 1,
+            },
+        );
+        self.queue.borrow_mut().submit(&[encoder.finish()]);
+
+        let mapped: Rc<RefCell<Option<Vec<u8>>>> = Rc::new(RefCell::new(None));
+        let mapped_callback: Rc<RefCell<Option<Vec<u8>>>> = mapped.clone();
+        buffer.map_read_async(0, buffer_size, move |result: BufferMapAsyncResult<&[u8]>| {
+            if let Ok(mapping) = result {
+                *mapped_callback.borrow_mut() = Some(mapping.data.to_vec());
+            }
+        });
+        self.device.poll(true);
+
+        let padded: Vec<u8> = mapped.borrow_mut().take().ok_or(AFError::FrameReadback)?;
+        let mut data: Vec<u8> = Vec::with_capacity((row_size * self.size.height) as usize);
+        for row in padded.chunks(row_pitch as usize) {
+            data.extend_from_slice(&row[..row_size as usize]);
+        }
+        if swap_red_blue {
+            for pixel in data.chunks_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        return Ok(AFOwnedImage {
+            size: self.size,
+            data,
+        });
+    }
+
+    // runs the callbacks of the reads which have finished; waiting
+    // blocks until all of the work submitted so far is done
+    pub fn poll(&self, wait: bool) {
+        self.device.poll(wait);
+        self.pending_reads.borrow_mut().retain(|read| !read.done.get());
+    }
+
+    // queues a write to be copied in by the next flush
+    fn queue_upload(&self, buffer: &Rc<Buffer>, offset: u64, data: &[u8]) {
+        let mut upload_ring = self.upload_ring.borrow_mut();
+        let data_offset: u64 = upload_ring.data.len() as u64;
+        upload_ring.data.extend_from_slice(data);
+        // keeps every write in a chunk aligned to 4 bytes
+        let padded: usize = upload_ring.data.len().div_ceil(4) * 4;
+        upload_ring.data.resize(padded, 0);
+
+        upload_ring.copies.push(AFStagedCopy {
+            buffer: buffer.clone(),
+            offset,
+            data_offset,
+            length: data.len() as u64,
+        });
+    }
+
+    // copies the queued writes in ahead of anything submitted afterwards
+    fn flush_uploads(&self) {
+        let mut upload_ring = self.upload_ring.borrow_mut();
+        if upload_ring.copies.is_empty() {
+            return;
+        }
+        let copies: Vec<AFStagedCopy> = std::mem::take(&mut upload_ring.copies);
+        let data: Vec<u8> = std::mem::take(&mut upload_ring.data);
+
+        let writes: Vec<(u64, u64)> = copies
+            .iter()
+            .map(|copy| (copy.data_offset, copy.length))
+            .collect::<Vec<_>>();
+        let mut batches: Vec<(usize, Range<usize>)> = Vec::new();
+        for (range, data_range) in batch_uploads(writes.as_slice(), upload_ring.chunk_size) {
+            let chunk: usize = upload_ring.acquire(
+                &self.device,
+                &data[data_range.start as usize..data_range.end as usize],
+            );
+            batches.push((chunk, range));
+        }
+        // reused chunks are idle, so polling maps and fills them straight away
+        self.device.poll(false);
+
+        let mut encoder: CommandEncoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+        for (chunk, range) in batches.iter() {
+            let data_start: u64 = copies[range.start].data_offset;
+            for copy in &copies[range.clone()] {
+                encoder.copy_buffer_to_buffer(
+                    &upload_ring.chunks[*chunk].buffer,
+                    copy.data_offset - data_start,
+                    &copy.buffer,
+                    copy.offset,
+                    copy.length,
+                );
+            }
+        }
+        self.queue.borrow_mut().submit(&[encoder.finish()]);
+
+        for (chunk, _) in batches.iter() {
+            upload_ring.chunks[*chunk].release();
+        }
+    }
+
+    // runs the compute commands in order, before anything drawn afterwards
+    pub fn dispatch(&self, commands: &[AFComputeCommand]) {
+        self.flush_uploads();
+        let mut encoder: CommandEncoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+        {
+            let mut compute_pass = encoder.begin_compute_pass();
+            for command in commands {
+                compute_pass.set_pipeline(&command.compute_pipeline);
+                for set in command.enabled_uniform_groups.clone() {
+                    compute_pass.set_bind_group(
+                        set,
+                        &command.bind_groups[set as usize],
+                        command.dynamic_offsets[set as usize].as_slice(),
+                    );
+                }
+                compute_pass.dispatch(
+                    command.workgroups[0],
+                    command.workgroups[1],
+                    command.workgroups[2],
+                );
+            }
+        }
+        self.queue.borrow_mut().submit(&[encoder.finish()]);
+    }
+}
+
+impl AFUploadRing {
+    fn new(chunk_size: u64) -> Self {
+        return AFUploadRing {
+            chunk_size,
+            chunks: Vec::new(),
+            cursor: 0,
+            data: Vec::new(),
+            copies: Vec::new(),
+        };
+    }
+
+    // fills the first free chunk big enough for the data, searching from the
+    // cursor on, or a new one if there's none; reused chunks are only filled
+    // once they've been mapped by a poll
+    fn acquire(&mut self, device: &Device, data: &[u8]) -> usize {
+        let chunk_count: usize = self.chunks.len();
+        let free_chunk: Option<usize> = (0..chunk_count)
+            .map(|i| (self.cursor + i) % chunk_count)
+            .find(|i| self.chunks[*i].free.get() && self.chunks[*i].size >= data.len() as u64);
+
+        let index: usize = match free_chunk {
+            Some(index) => {
+                let chunk: &AFStagingChunk = &self.chunks[index];
+                chunk.free.set(false);
+                let data: Vec<u8> = data.to_vec();
+                chunk.buffer.map_write_async(
+                    0,
+                    data.len() as u64,
+                    move |result: BufferMapAsyncResult<&mut [u8]>| {
+                        if let Ok(mapping) = result {
+                            mapping.data.copy_from_slice(data.as_slice());
+                        }
+                    },
+                );
+                index
+            }
+            None => {
+                let size: u64 = self.chunk_size.max(data.len() as u64);
+                let mapped = device.create_buffer_mapped::<u8>(
+                    size as usize,
+                    BufferUsage::MAP_WRITE | BufferUsage::COPY_SRC,
+                );
+                mapped.data[..data.len()].copy_from_slice(data);
+                self.chunks.push(AFStagingChunk {
+                    buffer: mapped.finish(),
+                    size,
+                    free: Rc::new(Cell::new(false)),
+                });
+                self.chunks.len() - 1
+            }
         };
+        self.cursor = (index + 1) % self.chunks.len();
+
+        return index;
+    }
+}
+
+// splits writes, given as their offset and length in the staged data, into
+// the batches of consecutive writes packed into each chunk, and the data each
+// batch covers; a write bigger than a chunk is a batch of its own
+fn batch_uploads(writes: &[(u64, u64)], chunk_size: u64) -> Vec<(Range<usize>, Range<u64>)> {
+    let mut batches: Vec<(Range<usize>, Range<u64>)> = Vec::new();
+    let mut start: usize = 0;
+    while start < writes.len() {
+        let data_start: u64 = writes[start].0;
+        let mut end: usize = start + 1;
+        while end < writes.len() && writes[end].0 + writes[end].1 - data_start <= chunk_size {
+            end += 1;
+        }
+        let data_end: u64 = writes[end - 1].0 + writes[end - 1].1;
+
+        batches.push((start..end, data_start..data_end));
+        start = end;
+    }
+
+    return batches;
+}
+
+impl AFStagingChunk {
+    // the chunk can only be mapped once the GPU has finished copying out of
+    // it, so the mapping's callback marks it free again
+    fn release(&self) {
+        let free: Rc<Cell<bool>> = self.free.clone();
+        self.buffer.map_write_async(
+            0,
+            self.size,
+            move |_: BufferMapAsyncResult<&mut [u8]>| {
+                free.set(true);
+            },
+        );
+    }
+}
+
+// wgpu 0.4 can't report failure here: it aborts the process when no adapter
+// of the backend exists, and panics when the extensions aren't supported
+fn request_device(config: &AFContextConfig) -> (Adapter, Device, Queue) {
+    let adapter: Adapter = Adapter::request(&RequestAdapterOptions {
+        power_preference: match config.power_preference {
+            AFPowerPreference::LowPower => PowerPreference::LowPower,
+            AFPowerPreference::Default => PowerPreference::Default,
+            AFPowerPreference::HighPower => PowerPreference::HighPerformance,
+        },
+        backends: match config.backend_lib {
+            AFBackendLibrary::Vulkan => BackendBit::VULKAN,
+            AFBackendLibrary::Metal => BackendBit::METAL,
+            AFBackendLibrary::OpenGL => BackendBit::GL,
+            AFBackendLibrary::DX12 => BackendBit::DX12,
+            AFBackendLibrary::DX11 => BackendBit::DX11,
+        },
+    })
+    .expect("wgpu always returns an adapter or aborts");
+
+    let (device, queue): (Device, Queue) = adapter.request_device(&DeviceDescriptor {
+        extensions: Extensions {
+            anisotropic_filtering: config.anisotropic_filtering,
+        },
+        limits: Limits::default(),
+    });
+
+    return (adapter, device, queue);
+}
+
+fn check_sample_count(sample_count: u32) -> Result<(), AFError> {
+    return match sample_count.is_power_of_two() && sample_count <= 32 {
+        true => Ok(()),
+        false => Err(AFError::InvalidSampleCount(sample_count)),
+    };
+}
+
+fn create_swap_chain(
+    device: &Device,
+    surface: &Surface,
+    format: TextureFormat,
+    size: AFSize2D<u32>,
+    present_mode: PresentMode,
+) -> SwapChain {
+    return device.create_swap_chain(surface, &SwapChainDescriptor {
+        usage: TextureUsage::OUTPUT_ATTACHMENT,
+        format,
+        width: size.width,
+        height: size.height,
+        present_mode,
+    });
+}
+
+// when multisampling, the multisampled target is drawn into and resolved to the frame
+fn frame_attachments<'a>(
+    frame_view: &'a TextureView,
+    multisampled_target: &'a Option<(Texture, TextureView)>,
+    depth_target: &'a Option<(Texture, TextureView)>,
+) -> AFAttachments<'a> {
+    return AFAttachments {
+        colours: vec![match multisampled_target {
+            Some((_, view)) => (view, Some(frame_view)),
+            None => (frame_view, None),
+        }],
+        depth: depth_target.as_ref().map(|(_, view)| view),
+    };
+}
+
+fn create_multisampled_target(
+    device: &Device,
+    format: TextureFormat,
+    size: AFSize2D<u32>,
+    sample_count: u32,
+) -> Option<(Texture, TextureView)> {
+    if sample_count == 1 {
+        return None;
     }
+
+    let texture: Texture = device.create_texture(&TextureDescriptor {
+        size: Extent3d {
+            width: size.width,
+            height: size.height,
+            depth: 1,
+        },
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsage::OUTPUT_ATTACHMENT,
+    });
+    let view: TextureView = texture.create_default_view();
+
+    return Some((texture, view));
+}
+
+fn create_depth_target(
+    device: &Device,
+    format: TextureFormat,
+    size: AFSize2D<u32>,
+    sample_count: u32,
+) -> (Texture, TextureView) {
+    let texture: Texture = device.create_texture(&TextureDescriptor {
+        size: Extent3d {
+            width: size.width,
+            height: size.height,
+            depth: 1,
+        },
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsage::OUTPUT_ATTACHMENT,
+    });
+    let view: TextureView = texture.create_default_view();
+
+    return (texture, view);
+}
+
+fn create_offscreen_target(
+    device: &Device,
+    format: TextureFormat,
+    size: AFSize2D<u32>,
+) -> (Texture, TextureView) {
+    let texture: Texture = device.create_texture(&TextureDescriptor {
+        size: Extent3d {
+            width: size.width,
+            height: size.height,
+            depth: 1,
+        },
+        array_layer_count: 1,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::COPY_SRC | TextureUsage::SAMPLED,
+    });
+    let view: TextureView = texture.create_default_view();
+
+    return (texture, view);
 }
 
 impl AFShaderConstructor<AFContext> for AFShaderModule {
-    fn new(context: &AFContext, config: &AFShaderConfig) -> Self {
-        let module: ShaderModule = context
-            .device
-            .create_shader_module(&read_spirv(std::io::Cursor::new(config.bytecode)).unwrap());
+    fn new(context: &AFContext, config: &AFShaderConfig) -> Result<Self, AFError> {
+        let spirv: Vec<u32> = read_spirv(std::io::Cursor::new(config.bytecode))
+            .map_err(|error| AFError::InvalidSpirv(error.to_string()))?;
+        let reflection: AFShaderReflection = AFShaderReflection::new(&spirv, config.entry_point)?;
+        if reflection.stage != config.stage {
+            return Err(AFError::EntryPointStageMismatch {
+                expected: config.stage,
+                stage: reflection.stage,
+            });
+        }
+        let module: ShaderModule = context.device.create_shader_module(&spirv);
 
-        return AFShaderModule {
+        return Ok(AFShaderModule {
             module,
-            stage: match config.stage {
-                AFShaderStage::None => ShaderStage::NONE,
-                AFShaderStage::Vertex => ShaderStage::VERTEX,
-                AFShaderStage::Fragment => ShaderStage::FRAGMENT,
-                AFShaderStage::Compute => ShaderStage::COMPUTE,
-            },
+            stage: config.stage,
             entry: config.entry_point.to_string(),
-        };
+            reflection,
+        });
     }
 }
 
-impl AFRenderPipelineConstructor<AFContext> for AFRenderPipeline {
-    fn new(context: &AFContext, config: &AFRenderPipelineConfig) -> Self {
-        unimplemented!()
+impl AFShaderModule {
+    // the inputs, outputs and bindings of the module's entry point
+    pub fn reflection(&self) -> &AFShaderReflection {
+        return &self.reflection;
     }
 }
 
-impl AFMainloop for AFContext {
-    fn mainloop<F: 'static, T: 'static>(context: AFContext, on_redraw: F, on_finish: T)
-    where
-        F: Fn(AFMainloopState) -> (),
-        T: Fn() -> (),
-    {
-        let AFContext{event_loop, window, ..} = context;
+impl AFRenderPipelineConstructor<AFContext, AFShaderModule> for AFRenderPipeline {
+    fn new(
+        context: &AFContext,
+        config: &AFRenderPipelineConfig<AFShaderModule>,
+    ) -> Result<Self, AFError> {
+        check_shader_stage(config.vertex_shader, AFShaderStage::Vertex)?;
+        check_vertex_inputs(config.vertex_shader, config.vertex_buffer_slots)?;
+        check_shader_bindings(config.vertex_shader, config.uniforms)?;
+        if let Some(fragment_shader) = config.fragment_shader {
+            check_shader_stage(fragment_shader, AFShaderStage::Fragment)?;
+            check_shader_bindings(fragment_shader, config.uniforms)?;
+        }
 
-        event_loop.run(move |event, event_loop, control_flow|{
-            *control_flow = ControlFlow::Poll;
+        let pass_format: AFPassFormat = match config.render_target {
+            Some(render_target) => render_target_pass_format(render_target),
+            None => context.frame_pass_format(),
+        };
 
-            match event {
-                Event::WindowEvent {window_id, event} => {
-                    // do stuff with the window event
-                },
-                Event::MainEventsCleared => {
-                    window.request_redraw();
-                },
-                Event::RedrawRequested(window_id) => {
-                    on_redraw(AFMainloopState{});
+        // each colour state has to match its attachment's format, in order
+        if config.colour_states.len() != pass_format.colour_formats.len()
+            || config
+                .colour_states
+                .iter()
+                .zip(pass_format.colour_formats.iter())
+                .any(|(colour_state, format)| texture_format(colour_state.format) != *format)
+        {
+            return Err(AFError::ColourStateMismatch);
+        }
+
+        // every pipeline has to match the context's depth texture, so pipelines
+        // without depth or stencil tests get a state that passes everything
+
+        let depth_stencil_state: Option<DepthStencilStateDescriptor> =
+            match (pass_format.depth_format, &config.depth_stencil) {
+                (None, None) => None,
+                (None, Some(_)) => return Err(AFError::NoDepthTarget),
+                (Some(format), None) => Some(DepthStencilStateDescriptor {
+                    format,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Always,
+                    stencil_front: StencilStateFaceDescriptor::IGNORE,
+                    stencil_back: StencilStateFaceDescriptor::IGNORE,
+                    stencil_read_mask: 0,
+                    stencil_write_mask: 0,
+                }),
+                (Some(format), Some(depth_stencil)) => Some(DepthStencilStateDescriptor {
+                    format,
+                    depth_write_enabled: depth_stencil.depth_write,
+                    depth_compare: compare_function(depth_stencil.depth_compare),
+                    stencil_front: stencil_face(&depth_stencil.stencil_front),
+                    stencil_back: stencil_face(&depth_stencil.stencil_back),
+                    stencil_read_mask: depth_stencil.stencil_read_mask,
+                    stencil_write_mask: depth_stencil.stencil_write_mask,
+                }),
+            };
+
+        let bindings: AFBindings = AFBindings::new(context, config.uniforms);
+
+        let pipeline_layout: PipelineLayout =
+            context
+                .device
+                .create_pipeline_layout(&PipelineLayoutDescriptor {
+                    bind_group_layouts: bindings
+                        .bind_group_layouts
+                        .iter()
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                });
+
+        // a stride of 0 reads the same element for every vertex or instance
+        for slot in config.vertex_buffer_slots.iter() {
+            for attrib in slot.attribs.iter() {
+                let size: u32 = attrib.vertex_format.size();
+                if slot.stride > 0 && attrib.offset as u64 + size as u64 > slot.stride {
+                    return Err(AFError::VertexAttribOutOfRange {
+                        location: attrib.location,
+                        offset: attrib.offset,
+                        size,
+                        stride: slot.stride,
+                    });
+                }
+            }
+        }
+
+        // the attribute lists are owned here so the
+        // vertex buffer descriptors can borrow them
+        let vertex_attribs: Vec<Vec<VertexAttributeDescriptor>> = config
+            .vertex_buffer_slots
+            .iter()
+            .map(|slot| {
+                slot.attribs
+                    .iter()
+                    .map(|attrib| VertexAttributeDescriptor {
+                        offset: attrib.offset as u64,
+                        format: vertex_format(attrib.vertex_format),
+                        shader_location: attrib.location,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let vertex_buffers: Vec<VertexBufferDescriptor> = config
+            .vertex_buffer_slots
+            .iter()
+            .zip(vertex_attribs.iter())
+            .map(|(slot, attribs)| VertexBufferDescriptor {
+                stride: slot.stride,
+                step_mode: match slot.step_mode {
+                    AFVertexStepMode::PerVertex => InputStepMode::Vertex,
+                    AFVertexStepMode::PerInstance => InputStepMode::Instance,
                 },
-                Event::LoopDestroyed => {
-                    on_finish();
+                attributes: attribs.as_slice(),
+            })
+            .collect::<Vec<_>>();
+
+        let render_pipeline: RenderPipeline =
+            context
+                .device
+                .create_render_pipeline(&RenderPipelineDescriptor {
+                    layout: &pipeline_layout,
+                    vertex_stage: ProgrammableStageDescriptor {
+                        module: &config.vertex_shader.module,
+                        entry_point: config.vertex_shader.entry.as_str(),
+                    },
+                    fragment_stage: config.fragment_shader.map(|fragment_shader| {
+                        ProgrammableStageDescriptor {
+                            module: &fragment_shader.module,
+                            entry_point: fragment_shader.entry.as_str(),
+                        }
+                    }),
+                    rasterization_state: Some(RasterizationStateDescriptor {
+                        front_face: match config.front_face {
+                            AFDirection::Clockwise => FrontFace::Cw,
+                            AFDirection::CounterClockwise => FrontFace::Ccw,
+                        },
+                        cull_mode: match config.cull_mode {
+                            None => CullMode::None,
+                            Some(AFFace::Front) => CullMode::Front,
+                            Some(AFFace::Back) => CullMode::Back,
+                        },
+                        depth_bias: 0,
+                        depth_bias_slope_scale: 0.0,
+                        depth_bias_clamp: 0.0,
+                    }),
+                    primitive_topology: match config.primitive {
+                        AFDrawablePrimitive::Points => PrimitiveTopology::PointList,
+                        AFDrawablePrimitive::Lines => PrimitiveTopology::LineList,
+                        AFDrawablePrimitive::LineStrip => PrimitiveTopology::LineStrip,
+                        AFDrawablePrimitive::Triangles => PrimitiveTopology::TriangleList,
+                        AFDrawablePrimitive::TriangleStrip => PrimitiveTopology::TriangleStrip,
+                    },
+                    color_states: config
+                        .colour_states
+                        .iter()
+                        .map(|colour_state| ColorStateDescriptor {
+                            format: texture_format(colour_state.format),
+                            color_blend: blend_descriptor(&colour_state.colour_blend),
+                            alpha_blend: blend_descriptor(&colour_state.alpha_blend),
+                            write_mask: colour_write_mask(colour_state.write_mask),
+                        })
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                    depth_stencil_state,
+                    index_format: match config.index_format {
+                        AFIndexFormat::UnsignedInt16 => IndexFormat::Uint16,
+                        AFIndexFormat::UnsignedInt32 => IndexFormat::Uint32,
+                    },
+                    vertex_buffers: vertex_buffers.as_slice(),
+                    sample_count: pass_format.sample_count,
+                    sample_mask: config.sample_mask,
+                    alpha_to_coverage_enabled: config.alpha_to_coverage,
+                });
+
+        return Ok(AFRenderPipeline {
+            render_pipeline: Rc::new(render_pipeline),
+            pass_format,
+            bindings,
+            index_format: config.index_format,
+            vertex_strides: config
+                .vertex_buffer_slots
+                .iter()
+                .map(|slot| match slot.step_mode {
+                    AFVertexStepMode::PerVertex => Some(slot.stride),
+                    AFVertexStepMode::PerInstance => None,
+                })
+                .collect::<Vec<_>>(),
+        });
+    }
+}
+
+// the uniform methods of a pipeline, which all forward to its bindings; commands
+// made before a resource is bound keep using the resources they were made with
+macro_rules! binding_methods {
+    () => {
+        pub fn bind_texture(
+            &mut self,
+            context: &AFContext,
+            set: u32,
+            location: u32,
+            texture: &AFTexture,
+        ) -> Result<(), AFError> {
+            return self.bindings.bind_texture(context, set, location, texture);
+        }
+
+        pub fn bind_sampler(
+            &mut self,
+            context: &AFContext,
+            set: u32,
+            location: u32,
+            sampler: &AFSampler,
+        ) -> Result<(), AFError> {
+            return self.bindings.bind_sampler(context, set, location, sampler);
+        }
+
+        pub fn bind_buffer(
+            &mut self,
+            context: &AFContext,
+            set: u32,
+            location: u32,
+            buffer: &AFBuffer,
+        ) -> Result<(), AFError> {
+            return self.bindings.bind_buffer(context, set, location, buffer);
+        }
+
+        pub fn write_uniform(
+            &self,
+            context: &AFContext,
+            set: u32,
+            location: u32,
+            data: &[u8],
+        ) -> Result<(), AFError> {
+            return self.bindings.write_uniform(context, set, location, data);
+        }
+
+        pub fn write_uniform_value<T: AsBytes>(
+            &self,
+            context: &AFContext,
+            set: u32,
+            location: u32,
+            value: &T,
+        ) -> Result<(), AFError> {
+            return self.bindings.write_uniform_value(context, set, location, value);
+        }
+
+        pub fn write_uniform_slot(
+            &self,
+            context: &AFContext,
+            set: u32,
+            location: u32,
+            slot: u32,
+            data: &[u8],
+        ) -> Result<(), AFError> {
+            return self
+                .bindings
+                .write_uniform_slot(context, set, location, slot, data);
+        }
+    };
+}
+
+impl AFRenderPipeline {
+    binding_methods!();
+}
+
+impl AFComputePipeline {
+    binding_methods!();
+}
+
+impl AFBindings {
+    fn new(context: &AFContext, uniforms: &[AFUniformGroup]) -> AFBindings {
+        // one bind group per set; sets skipped in the config get an empty group
+        // so that the set numbers line up with the bind group indices
+        let set_count: u32 = uniforms
+            .iter()
+            .map(|group| group.set + 1)
+            .max()
+            .unwrap_or(0);
+        let groups: Vec<Option<&AFUniformGroup>> = (0..set_count)
+            .map(|set| uniforms.iter().find(|group| group.set == set))
+            .collect::<Vec<_>>();
+
+        let bind_group_layouts: Vec<BindGroupLayout> = groups
+            .iter()
+            .map(|group| {
+                let bindings: Vec<BindGroupLayoutBinding> = match group {
+                    Some(group) => group
+                        .uniforms
+                        .iter()
+                        .map(|uniform| BindGroupLayoutBinding {
+                            binding: uniform.location,
+                            visibility: shader_stage(uniform.stage),
+                            ty: match uniform.uniform_type {
+                                AFUniformType::Buffer => BindingType::UniformBuffer {
+                                    dynamic: uniform.dynamic,
+                                },
+                                AFUniformType::Sampler => BindingType::Sampler,
+                                AFUniformType::Storage => BindingType::StorageBuffer {
+                                    dynamic: uniform.dynamic,
+                                    readonly: false,
+                                },
+                                AFUniformType::ReadOnlyStorage => BindingType::StorageBuffer {
+                                    dynamic: uniform.dynamic,
+                                    readonly: true,
+                                },
+                                AFUniformType::Texture => BindingType::SampledTexture {
+                                    multisampled: false,
+                                    dimension: TextureViewDimension::D2,
+                                },
+                            },
+                        })
+                        .collect::<Vec<_>>(),
+                    None => Vec::new(),
+                };
+
+                context
+                    .device
+                    .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                        bindings: bindings.as_slice(),
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        // every slot starts with a resource of its own, so the bind groups are
+        // complete; textures and samplers can be swapped for others afterwards
+        let placeholder_texture: Rc<Texture> = Rc::new(context.device.create_texture(
+            &TextureDescriptor {
+                size: Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth: 1,
                 },
-                _ => {},
+                array_layer_count: 1,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsage::SAMPLED,
+            },
+        ));
+        let placeholder_view: Rc<TextureView> = Rc::new(placeholder_texture.create_default_view());
+        let default_sampler: Rc<Sampler> = Rc::new(create_sampler(
+            &context.device,
+            &AFSamplerConfig {
+                mag_filter: AFFilterMode::Linear,
+                min_filter: AFFilterMode::Linear,
+                mipmap_filter: AFFilterMode::Nearest,
+                address_mode_u: AFAddressMode::ClampToEdge,
+                address_mode_v: AFAddressMode::ClampToEdge,
+                address_mode_w: AFAddressMode::ClampToEdge,
+                lod_min_clamp: 0.0,
+                lod_max_clamp: 0.0,
+                anisotropic: context.anisotropic_filtering,
+            },
+        ));
+
+        let mut resources: HashMap<(u32, u32), AFUniformResource> = HashMap::new();
+        for group in uniforms {
+            for uniform in group.uniforms {
+                let resource: AFUniformResource = match uniform.uniform_type {
+                    AFUniformType::Buffer => AFUniformResource::Buffer(Rc::new(
+                        context.device.create_buffer(&BufferDescriptor {
+                            size: uniform_buffer_size(uniform),
+                            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+                        }),
+                    )),
+                    AFUniformType::Storage | AFUniformType::ReadOnlyStorage => {
+                        AFUniformResource::Buffer(Rc::new(context.device.create_buffer(
+                            &BufferDescriptor {
+                                size: uniform_buffer_size(uniform),
+                                usage: BufferUsage::STORAGE
+                                    | BufferUsage::COPY_DST
+                                    | BufferUsage::COPY_SRC,
+                            },
+                        )))
+                    }
+                    AFUniformType::Sampler => AFUniformResource::Sampler(default_sampler.clone()),
+                    AFUniformType::Texture => AFUniformResource::Texture(
+                        placeholder_texture.clone(),
+                        placeholder_view.clone(),
+                    ),
+                };
+                resources.insert((group.set, uniform.location), resource);
             }
-        });
+        }
+
+        let uniform_groups: Vec<Vec<AFUniform>> = groups
+            .iter()
+            .map(|group| match group {
+                Some(group) => group.uniforms.to_vec(),
+                None => Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let bind_groups: Vec<BindGroup> = create_bind_groups(
+            &context.device,
+            bind_group_layouts.as_slice(),
+            uniform_groups.as_slice(),
+            &resources,
+        );
+
+        return AFBindings {
+            bind_groups: Rc::new(bind_groups),
+            bind_group_layouts,
+            uniform_groups,
+            resources,
+        };
+    }
+
+    fn bind_texture(
+        &mut self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        texture: &AFTexture,
+    ) -> Result<(), AFError> {
+        self.find_uniform(set, location, &[AFUniformType::Texture])?;
+
+        return self.bind(
+            context,
+            set,
+            location,
+            AFUniformResource::Texture(texture.texture.clone(), texture.view.clone()),
+        );
+    }
+
+    fn bind_sampler(
+        &mut self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        sampler: &AFSampler,
+    ) -> Result<(), AFError> {
+        self.find_uniform(set, location, &[AFUniformType::Sampler])?;
+
+        return self.bind(
+            context,
+            set,
+            location,
+            AFUniformResource::Sampler(sampler.sampler.clone()),
+        );
+    }
+
+    // binds a buffer in place of the one a buffer or storage uniform was made
+    // with; it must have the matching usage and be at least as big as the slot
+    fn bind_buffer(
+        &mut self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        buffer: &AFBuffer,
+    ) -> Result<(), AFError> {
+        let uniform: &AFUniform = self.find_uniform(set, location, BUFFER_UNIFORM_TYPES)?;
+        match uniform.uniform_type {
+            AFUniformType::Buffer if !buffer.usage.uniform => {
+                return Err(AFError::MissingBufferUsage("uniform"));
+            }
+            AFUniformType::Storage | AFUniformType::ReadOnlyStorage if !buffer.usage.storage => {
+                return Err(AFError::MissingBufferUsage("storage"));
+            }
+            _ => {}
+        }
+        if buffer.byte_size < uniform_buffer_size(uniform) {
+            return Err(AFError::UniformSizeMismatch {
+                set,
+                location,
+                byte_size: uniform.byte_size,
+                length: buffer.byte_size as usize,
+            });
+        }
+
+        return self.bind(
+            context,
+            set,
+            location,
+            AFUniformResource::Buffer(buffer.buffer.clone()),
+        );
+    }
+
+    // writes a whole uniform, or the first slot of a dynamic one
+    fn write_uniform(
+        &self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        data: &[u8],
+    ) -> Result<(), AFError> {
+        return self.write_uniform_slot(context, set, location, 0, data);
+    }
+
+    fn write_uniform_value<T: AsBytes>(
+        &self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        value: &T,
+    ) -> Result<(), AFError> {
+        return self.write_uniform_slot(context, set, location, 0, value.as_bytes());
+    }
+
+    // the data is copied in by the next draw or dispatch; render
+    // commands share the uniform buffers, so they all see the write
+    fn write_uniform_slot(
+        &self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        slot: u32,
+        data: &[u8],
+    ) -> Result<(), AFError> {
+        let uniform: &AFUniform = self.find_uniform(set, location, BUFFER_UNIFORM_TYPES)?;
+        if data.len() != uniform.byte_size as usize {
+            return Err(AFError::UniformSizeMismatch {
+                set,
+                location,
+                byte_size: uniform.byte_size,
+                length: data.len(),
+            });
+        }
+        let offset: u64 = uniform_slot_offset(set, uniform, slot)?;
+
+        let buffer: &Rc<Buffer> = match &self.resources[&(set, location)] {
+            AFUniformResource::Buffer(buffer) => buffer,
+            _ => unreachable!("buffer uniforms are always backed by buffers"),
+        };
+        context.queue_upload(buffer, offset, data);
+
+        return Ok(());
+    }
+
+    // finds the uniform in the slot if it has one of the given types
+    fn find_uniform(
+        &self,
+        set: u32,
+        location: u32,
+        uniform_types: &[AFUniformType],
+    ) -> Result<&AFUniform, AFError> {
+        return self
+            .uniform_groups
+            .get(set as usize)
+            .and_then(|uniforms| {
+                uniforms.iter().find(|uniform| {
+                    uniform.location == location && uniform_types.contains(&uniform.uniform_type)
+                })
+            })
+            .ok_or(AFError::NoSuchUniform {
+                set,
+                location,
+                uniform_type: uniform_types[0],
+            });
+    }
+
+    fn bind(
+        &mut self,
+        context: &AFContext,
+        set: u32,
+        location: u32,
+        resource: AFUniformResource,
+    ) -> Result<(), AFError> {
+        self.resources.insert((set, location), resource);
+        self.bind_groups = Rc::new(create_bind_groups(
+            &context.device,
+            self.bind_group_layouts.as_slice(),
+            self.uniform_groups.as_slice(),
+            &self.resources,
+        ));
+
+        return Ok(());
+    }
+
+    // every dynamic uniform needs an offset, in the order of their locations
+    fn dynamic_offsets(&self, dynamic_slots: &[Vec<u32>]) -> Result<Vec<Vec<u64>>, AFError> {
+        let mut dynamic_offsets: Vec<Vec<u64>> = Vec::new();
+        for (set, uniforms) in self.uniform_groups.iter().enumerate() {
+            let mut dynamic_uniforms: Vec<&AFUniform> =
+                uniforms.iter().filter(|uniform| uniform.dynamic).collect::<Vec<_>>();
+            dynamic_uniforms.sort_by_key(|uniform| uniform.location);
+
+            let slots: &[u32] = dynamic_slots.get(set).map_or(&[], |slots| slots.as_slice());
+            let offsets: Vec<u64> = dynamic_uniforms
+                .iter()
+                .enumerate()
+                .map(|(i, uniform)| {
+                    uniform_slot_offset(set as u32, uniform, slots.get(i).copied().unwrap_or(0))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            dynamic_offsets.push(offsets);
+        }
+
+        return Ok(dynamic_offsets);
+    }
+
+    // commands index the bind groups by every set they enable
+    fn check_enabled_groups(&self, enabled: &Range<u32>) -> Result<(), AFError> {
+        return match enabled.end as usize <= self.bind_groups.len() {
+            true => Ok(()),
+            false => Err(AFError::UniformGroupsOutOfRange {
+                enabled: enabled.clone(),
+                groups: self.bind_groups.len() as u32,
+            }),
+        };
+    }
+}
+
+const BUFFER_UNIFORM_TYPES: &[AFUniformType] = &[
+    AFUniformType::Buffer,
+    AFUniformType::Storage,
+    AFUniformType::ReadOnlyStorage,
+];
+
+// dynamic uniforms hold each slot at an offset aligned to 256 bytes,
+// the most any backend asks of dynamic offsets
+const DYNAMIC_OFFSET_ALIGNMENT: u64 = 256;
+
+fn uniform_stride(uniform: &AFUniform) -> u64 {
+    return (uniform.byte_size as u64).div_ceil(DYNAMIC_OFFSET_ALIGNMENT) * DYNAMIC_OFFSET_ALIGNMENT;
+}
+
+fn uniform_buffer_size(uniform: &AFUniform) -> u64 {
+    return match uniform.dynamic {
+        true => uniform_stride(uniform) * uniform.slots.max(1) as u64,
+        false => uniform.byte_size as u64,
+    };
+}
+
+fn uniform_slot_offset(set: u32, uniform: &AFUniform, slot: u32) -> Result<u64, AFError> {
+    let slots: u32 = match uniform.dynamic {
+        true => uniform.slots.max(1),
+        false => 1,
+    };
+
+    return match slot < slots {
+        true => Ok(uniform_stride(uniform) * slot as u64),
+        false => Err(AFError::UniformSlotOutOfRange {
+            set,
+            location: uniform.location,
+            slot,
+            slots,
+        }),
+    };
+}
+
+// one bind group per set, in set order
+fn create_bind_groups(
+    device: &Device,
+    layouts: &[BindGroupLayout],
+    uniform_groups: &[Vec<AFUniform>],
+    resources: &HashMap<(u32, u32), AFUniformResource>,
+) -> Vec<BindGroup> {
+    return uniform_groups
+        .iter()
+        .zip(layouts.iter())
+        .enumerate()
+        .map(|(set, (uniforms, layout))| {
+            let bindings: Vec<Binding> = uniforms
+                .iter()
+                .map(|uniform| Binding {
+                    binding: uniform.location,
+                    resource: match &resources[&(set as u32, uniform.location)] {
+                        AFUniformResource::Buffer(buffer) => BindingResource::Buffer {
+                            buffer,
+                            range: 0..uniform.byte_size as u64,
+                        },
+                        AFUniformResource::Sampler(sampler) => BindingResource::Sampler(sampler),
+                        AFUniformResource::Texture(_, view) => BindingResource::TextureView(view),
+                    },
+                })
+                .collect::<Vec<_>>();
+
+            device.create_bind_group(&BindGroupDescriptor {
+                layout,
+                bindings: bindings.as_slice(),
+            })
+        })
+        .collect::<Vec<_>>();
+}
+
+impl AFTextureConstructor<AFContext> for AFTexture {
+    fn new(context: &AFContext, config: &AFTextureConfig) -> Result<Self, AFError> {
+        config.image.validate()?;
+        let format: TextureFormat = match config.format {
+            AFTextureFormat::Rgba8Unorm | AFTextureFormat::Rgba8UnormSrgb => {
+                texture_format(config.format)
+            }
+            format => return Err(AFError::UnsupportedTextureFormat(format)),
+        };
+
+        let size: AFSize2D<u32> = config.image.size;
+        // an empty image would have no mip levels at all
+        if size.width == 0 || size.height == 0 {
+            return Err(AFError::EmptyImage);
+        }
+        let full_chain: u32 = 32 - size.width.max(size.height).leading_zeros();
+        let mip_level_count: u32 = match config.mip_levels {
+            0 => full_chain,
+            levels => levels.min(full_chain),
+        };
+
+        let texture: Texture = context.device.create_texture(&TextureDescriptor {
+            size: Extent3d {
+                width: size.width,
+                height: size.height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+        });
+
+        let mut encoder: CommandEncoder = context
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+
+        // the mip levels are box filtered on the CPU, each from the one before
+        let mut level: AFOwnedImage = AFOwnedImage {
+            size,
+            data: config.image.data.to_vec(),
+        };
+        let mut _uploaded: Vec<Buffer> = Vec::new();
+        for mip_level in 0..mip_level_count {
+            if mip_level > 0 {
+                level = downsample(&level);
+            }
+            _uploaded.push(upload_image(
+                &context.device,
+                &mut encoder,
+                &texture,
+                mip_level,
+                level.as_image(),
+            ));
+        }
+        context.queue.borrow_mut().submit(&[encoder.finish()]);
+
+        let view: TextureView = texture.create_default_view();
+
+        return Ok(AFTexture {
+            texture: Rc::new(texture),
+            view: Rc::new(view),
+        });
+    }
+}
+
+// copies an image into a mip level of a texture through a staging buffer,
+// which must be kept alive until the encoder is submitted
+fn upload_image(
+    device: &Device,
+    encoder: &mut CommandEncoder,
+    texture: &Texture,
+    mip_level: u32,
+    image: AFImage,
+) -> Buffer {
+    // rows of a buffer copy must be aligned to 256 bytes
+    let row_size: usize = image.size.width as usize * 4;
+    let row_pitch: usize = row_size.div_ceil(256) * 256;
+
+    let mut padded: Vec<u8> = vec![0; row_pitch * image.size.height as usize];
+    for (row, pixels) in padded.chunks_mut(row_pitch).zip(image.data.chunks(row_size)) {
+        row[..row_size].copy_from_slice(pixels);
+    }
+
+    let buffer: Buffer = device
+        .create_buffer_mapped(padded.len(), BufferUsage::COPY_SRC)
+        .fill_from_slice(padded.as_slice());
+    encoder.copy_buffer_to_texture(
+        BufferCopyView {
+            buffer: &buffer,
+            offset: 0,
+            row_pitch: row_pitch as u32,
+            image_height: image.size.height,
+        },
+        TextureCopyView {
+            texture,
+            mip_level,
+            array_layer: 0,
+            origin: Origin3d {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        },
+        Extent3d {
+            width: image.size.width,
+            height: image.size.height,
+            depth: 1,
+        },
+    );
+
+    return buffer;
+}
+
+// halves an image, averaging each 2x2 block of pixels
+fn downsample(image: &AFOwnedImage) -> AFOwnedImage {
+    let size: AFSize2D<u32> = AFSize2D {
+        width: (image.size.width / 2).max(1),
+        height: (image.size.height / 2).max(1),
+    };
+
+    let mut data: Vec<u8> = Vec::with_capacity(size.width as usize * size.height as usize * 4);
+    for y in 0..size.height {
+        for x in 0..size.width {
+            for channel in 0..4 {
+                let mut sum: u32 = 0;
+                for (dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let source_x: u32 = (x * 2 + dx).min(image.size.width - 1);
+                    let source_y: u32 = (y * 2 + dy).min(image.size.height - 1);
+                    let index: usize =
+                        ((source_y * image.size.width + source_x) * 4 + channel) as usize;
+                    sum += image.data[index] as u32;
+                }
+                data.push((sum / 4) as u8);
+            }
+        }
+    }
+
+    return AFOwnedImage { size, data };
+}
+
+impl AFBufferConstructor<AFContext> for AFBuffer {
+    fn new(context: &AFContext, config: &AFBufferConfig) -> Result<Self, AFError> {
+        if let Some(index_format) = config.usage.index {
+            if !config.data.len().is_multiple_of(index_size(index_format)) {
+                return Err(AFError::MisalignedIndexData {
+                    index_format,
+                    length: config.data.len(),
+                });
+            }
+        }
+
+        // every buffer can be written to and read back
+        let mut usage: BufferUsage = BufferUsage::COPY_DST | BufferUsage::COPY_SRC;
+        if config.usage.vertex {
+            usage |= BufferUsage::VERTEX;
+        }
+        if config.usage.index.is_some() {
+            usage |= BufferUsage::INDEX;
+        }
+        if config.usage.uniform {
+            usage |= BufferUsage::UNIFORM;
+        }
+        if config.usage.storage {
+            usage |= BufferUsage::STORAGE;
+        }
+
+        // copies have to be whole words, so the buffer is padded to 4
+        // bytes for a write ending at its last byte to be copied in whole
+        let mut data: Vec<u8> = config.data.to_vec();
+        data.resize(config.data.len().div_ceil(4) * 4, 0);
+        let buffer: Buffer = context
+            .device
+            .create_buffer_mapped(data.len(), usage)
+            .fill_from_slice(data.as_slice());
+
+        return Ok(AFBuffer {
+            buffer: Rc::new(buffer),
+            byte_size: config.data.len() as u64,
+            usage: config.usage,
+            indices: config
+                .usage
+                .index
+                .map(|_| Rc::new(RefCell::new(config.data.to_vec()))),
+        });
+    }
+}
+
+impl AFBuffer {
+    // the data is copied in by the next draw or dispatch, so commands made earlier
+    // draw with it too; offsets must be aligned to 4, and so must lengths unless
+    // the write runs to the end of the buffer
+    pub fn write(&self, context: &AFContext, offset: u64, data: &[u8]) -> Result<(), AFError> {
+        if offset + data.len() as u64 > self.byte_size {
+            return Err(AFError::BufferWriteOutOfRange {
+                offset,
+                length: data.len(),
+                byte_size: self.byte_size,
+            });
+        }
+        let to_end: bool = offset + data.len() as u64 == self.byte_size;
+        if !offset.is_multiple_of(4) || (!data.len().is_multiple_of(4) && !to_end) {
+            return Err(AFError::MisalignedBufferWrite {
+                offset,
+                length: data.len(),
+            });
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(index_format) = self.usage.index {
+            if !data.len().is_multiple_of(index_size(index_format)) {
+                return Err(AFError::MisalignedIndexData {
+                    index_format,
+                    length: data.len(),
+                });
+            }
+        }
+        if let Some(indices) = &self.indices {
+            indices.borrow_mut()[offset as usize..offset as usize + data.len()]
+                .copy_from_slice(data);
+        }
+
+        // the padding lands in the buffer's own padding
+        match data.len().is_multiple_of(4) {
+            true => context.queue_upload(&self.buffer, offset, data),
+            false => {
+                let mut padded: Vec<u8> = data.to_vec();
+                padded.resize(data.len().div_ceil(4) * 4, 0);
+                context.queue_upload(&self.buffer, offset, padded.as_slice());
+            }
+        }
+
+        return Ok(());
+    }
+
+    pub fn write_value<T: AsBytes + ?Sized>(
+        &self,
+        context: &AFContext,
+        offset: u64,
+        value: &T,
+    ) -> Result<(), AFError> {
+        return self.write(context, offset, value.as_bytes());
+    }
+
+    // writes indices starting from the given one, in the buffer's index format; 16 bit
+    // indices are written in pairs, so an odd number can only be written up to the end
+    pub fn write_indices(
+        &self,
+        context: &AFContext,
+        first_index: u64,
+        indices: &AFIndexData,
+    ) -> Result<(), AFError> {
+        let index_format: AFIndexFormat = match self.usage.index {
+            Some(index_format) => index_format,
+            None => return Err(AFError::MissingBufferUsage("index")),
+        };
+        let data: &[u8] = match (indices, index_format) {
+            (AFIndexData::UnsignedInt16(indices), AFIndexFormat::UnsignedInt16) => {
+                indices.as_bytes()
+            }
+            (AFIndexData::UnsignedInt32(indices), AFIndexFormat::UnsignedInt32) => {
+                indices.as_bytes()
+            }
+            (AFIndexData::UnsignedInt16(_), AFIndexFormat::UnsignedInt32) => {
+                return Err(AFError::IndexFormatMismatch {
+                    pipeline: AFIndexFormat::UnsignedInt32,
+                    data: AFIndexFormat::UnsignedInt16,
+                });
+            }
+            (AFIndexData::UnsignedInt32(_), AFIndexFormat::UnsignedInt16) => {
+                return Err(AFError::IndexFormatMismatch {
+                    pipeline: AFIndexFormat::UnsignedInt16,
+                    data: AFIndexFormat::UnsignedInt32,
+                });
+            }
+            (AFIndexData::Bytes(bytes), _) => {
+                if !bytes.len().is_multiple_of(index_size(index_format)) {
+                    return Err(AFError::MisalignedIndexData {
+                        index_format,
+                        length: bytes.len(),
+                    });
+                }
+                bytes.as_slice()
+            }
+        };
+
+        return self.write(
+            context,
+            first_index * index_size(index_format) as u64,
+            data,
+        );
+    }
+
+    // copies the buffer out once the work submitted before it is done; the callback
+    // is called from a later AFContext::poll, which the mainloop does every frame
+    pub fn read<F>(&self, context: &AFContext, callback: F)
+    where
+        F: 'static + FnOnce(Result<Vec<u8>, AFError>),
+    {
+        context.flush_uploads();
+
+        // the padding is copied too, and cut off once it's read
+        let padded_size: u64 = self.byte_size.div_ceil(4) * 4;
+        let byte_size: usize = self.byte_size as usize;
+        let staging: Buffer = context.device.create_buffer(&BufferDescriptor {
+            size: padded_size,
+            usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+        });
+
+        let mut encoder: CommandEncoder = context
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { todo: 0 });
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &staging, 0, padded_size);
+        context.queue.borrow_mut().submit(&[encoder.finish()]);
+
+        let done: Rc<Cell<bool>> = Rc::new(Cell::new(false));
+        let done_callback: Rc<Cell<bool>> = done.clone();
+        staging.map_read_async(
+            0,
+            padded_size,
+            move |result: BufferMapAsyncResult<&[u8]>| {
+                done_callback.set(true);
+                callback(match result {
+                    Ok(mapping) => Ok(mapping.data[..byte_size].to_vec()),
+                    Err(()) => Err(AFError::BufferReadback),
+                });
+            },
+        );
+
+        context.pending_reads.borrow_mut().push(AFPendingRead {
+            buffer: staging,
+            done,
+        });
+    }
+
+    pub fn byte_size(&self) -> u64 {
+        return self.byte_size;
+    }
+}
+
+impl AFSamplerConstructor<AFContext> for AFSampler {
+    fn new(context: &AFContext, config: &AFSamplerConfig) -> Result<Self, AFError> {
+        if config.anisotropic && !context.anisotropic_filtering {
+            return Err(AFError::AnisotropyNotEnabled);
+        }
+
+        return Ok(AFSampler {
+            sampler: Rc::new(create_sampler(&context.device, config)),
+        });
+    }
+}
+
+fn create_sampler(device: &Device, config: &AFSamplerConfig) -> Sampler {
+    // wgpu can't set the anisotropy level of a sampler yet, so anisotropic samplers
+    // only get the trilinear filtering that anisotropic filtering builds on,
+    // replacing the filters they were given
+    let (mag_filter, min_filter, mipmap_filter): (AFFilterMode, AFFilterMode, AFFilterMode) =
+        match config.anisotropic {
+            true => (AFFilterMode::Linear, AFFilterMode::Linear, AFFilterMode::Linear),
+            false => (config.mag_filter, config.min_filter, config.mipmap_filter),
+        };
+
+    return device.create_sampler(&SamplerDescriptor {
+        address_mode_u: address_mode(config.address_mode_u),
+        address_mode_v: address_mode(config.address_mode_v),
+        address_mode_w: address_mode(config.address_mode_w),
+        mag_filter: filter_mode(mag_filter),
+        min_filter: filter_mode(min_filter),
+        mipmap_filter: filter_mode(mipmap_filter),
+        lod_min_clamp: config.lod_min_clamp,
+        lod_max_clamp: config.lod_max_clamp,
+        compare_function: CompareFunction::Always,
+    });
+}
+
+fn check_shader_stage(shader: &AFShaderModule, slot: AFShaderStage) -> Result<(), AFError> {
+    return match shader.stage == slot {
+        true => Ok(()),
+        false => Err(AFError::ShaderStageMismatch {
+            slot,
+            stage: shader.stage,
+        }),
+    };
+}
+
+// every location the vertex shader reads has to be fed by an attribute of the same
+// kind; the component counts can differ, as missing components are filled in
+fn check_vertex_inputs(
+    shader: &AFShaderModule,
+    vertex_buffer_slots: &[AFVertexBuffer],
+) -> Result<(), AFError> {
+    for input in shader.reflection.inputs.iter() {
+        let attrib: &AFVertexAttrib = match vertex_buffer_slots
+            .iter()
+            .flat_map(|slot| slot.attribs.iter())
+            .find(|attrib| attrib.location == input.location)
+        {
+            Some(attrib) => attrib,
+            None => {
+                return Err(AFError::MissingVertexAttrib {
+                    location: input.location,
+                });
+            }
+        };
+
+        if input.scalar_type != Some(attrib.vertex_format.scalar_type()) {
+            return Err(AFError::VertexAttribMismatch {
+                location: input.location,
+                vertex_format: attrib.vertex_format,
+                scalar_type: input.scalar_type,
+            });
+        }
+    }
+
+    return Ok(());
+}
+
+// every binding the shader uses has to be a uniform of the pipeline visible to
+// the shader's stage, with buffers at least the size of the shader's block
+fn check_shader_bindings(
+    shader: &AFShaderModule,
+    uniform_groups: &[AFUniformGroup],
+) -> Result<(), AFError> {
+    for binding in shader.reflection.bindings.iter() {
+        let uniform: &AFUniform = match uniform_groups
+            .iter()
+            .filter(|group| group.set == binding.set)
+            .flat_map(|group| group.uniforms.iter())
+            .find(|uniform| uniform.location == binding.binding)
+        {
+            Some(uniform) => uniform,
+            None => {
+                return Err(AFError::MissingUniform {
+                    set: binding.set,
+                    location: binding.binding,
+                    stage: shader.stage,
+                });
+            }
+        };
+
+        let compatible: bool = match binding.binding_type {
+            AFShaderBindingType::UniformBuffer => uniform.uniform_type == AFUniformType::Buffer,
+            AFShaderBindingType::StorageBuffer => {
+                uniform.uniform_type == AFUniformType::Storage
+                    || uniform.uniform_type == AFUniformType::ReadOnlyStorage
+            }
+            AFShaderBindingType::Sampler => uniform.uniform_type == AFUniformType::Sampler,
+            AFShaderBindingType::Texture => uniform.uniform_type == AFUniformType::Texture,
+            // none of the uniform types bind storage textures
+            AFShaderBindingType::StorageTexture => false,
+        };
+        if !compatible {
+            return Err(AFError::UniformTypeMismatch {
+                set: binding.set,
+                location: binding.binding,
+                uniform_type: uniform.uniform_type,
+                binding_type: binding.binding_type,
+            });
+        }
+
+        if uniform.stage != shader.stage {
+            return Err(AFError::UniformNotVisible {
+                set: binding.set,
+                location: binding.binding,
+                stage: shader.stage,
+                visible: uniform.stage,
+            });
+        }
+
+        // a buffer can be bigger than the block, like a vec4 backing a vec3
+        if let Some(block_size) = binding.block_size {
+            if (uniform.byte_size as u64) < block_size {
+                return Err(AFError::UniformBlockSizeMismatch {
+                    set: binding.set,
+                    location: binding.binding,
+                    byte_size: uniform.byte_size,
+                    block_size,
+                });
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+impl AFRenderCommandConstructor<AFRenderPipeline, AFBuffer> for AFRenderCommand {
+    fn new(
+        pipeline: &AFRenderPipeline,
+        config: &AFRenderCommandConfig<AFBuffer>,
+    ) -> Result<Self, AFError> {
+        pipeline
+            .bindings
+            .check_enabled_groups(&config.enabled_uniform_groups)?;
+        let dynamic_offsets: Vec<Vec<u64>> =
+            pipeline.bindings.dynamic_offsets(config.dynamic_slots.as_slice())?;
+        match &config.command_type {
+            AFRenderCommandType::Empty => {}
+            AFRenderCommandType::Vertex { vertex_buffers } => {
+                check_vertex_buffers(pipeline, vertex_buffers)?;
+            }
+            AFRenderCommandType::Indices {
+                vertex_buffers,
+                index_buffer,
+            } => {
+                check_vertex_buffers(pipeline, vertex_buffers)?;
+                check_indices(pipeline, vertex_buffers, index_buffer, config.vertex_count)?;
+            }
+        }
+
+        return Ok(AFRenderCommand {
+            render_pipeline: pipeline.render_pipeline.clone(),
+            pass_format: pipeline.pass_format.clone(),
+            bind_groups: pipeline.bindings.bind_groups.clone(),
+            colour: config.colour,
+            clear_depth: config.clear_depth,
+            clear_stencil: config.clear_stencil,
+            vertex_count: config.vertex_count,
+            enabled_uniform_groups: config.enabled_uniform_groups.clone(),
+            dynamic_offsets,
+            calls: config.calls,
+            command_type: config.command_type.clone(),
+            blend_colour: config.blend_colour,
+            viewport: config.viewport,
+            scissor_rect: config.scissor_rect,
+            stencil_reference: config.stencil_reference,
+        });
+    }
+}
+
+// every slot of the pipeline needs a vertex buffer, or it would draw from an unbound one
+fn check_vertex_buffers(
+    pipeline: &AFRenderPipeline,
+    vertex_buffers: &[AFBuffer],
+) -> Result<(), AFError> {
+    if vertex_buffers.len() != pipeline.vertex_strides.len() {
+        return Err(AFError::VertexBufferCountMismatch {
+            slots: pipeline.vertex_strides.len(),
+            buffers: vertex_buffers.len(),
+        });
+    }
+
+    return match vertex_buffers.iter().all(|buffer| buffer.usage.vertex) {
+        true => Ok(()),
+        false => Err(AFError::MissingBufferUsage("vertex")),
+    };
+}
+
+fn index_size(index_format: AFIndexFormat) -> usize {
+    return match index_format {
+        AFIndexFormat::UnsignedInt16 => 2,
+        AFIndexFormat::UnsignedInt32 => 4,
+    };
+}
+
+fn read_index(index_format: AFIndexFormat, index: &[u8]) -> u32 {
+    return match index_format {
+        AFIndexFormat::UnsignedInt16 => u16::from_ne_bytes([index[0], index[1]]) as u32,
+        AFIndexFormat::UnsignedInt32 => u32::from_ne_bytes([index[0], index[1], index[2], index[3]]),
+    };
+}
+
+// checks the index buffer is in the pipeline's format and that
+// the indices drawn stay within every per vertex buffer
+fn check_indices(
+    pipeline: &AFRenderPipeline,
+    vertex_buffers: &[AFBuffer],
+    index_buffer: &AFBuffer,
+    vertex_count: u32,
+) -> Result<(), AFError> {
+    let (index_format, index_data): (AFIndexFormat, &RefCell<Vec<u8>>) =
+        match (index_buffer.usage.index, &index_buffer.indices) {
+            (Some(index_format), Some(index_data)) => (index_format, index_data),
+            _ => return Err(AFError::MissingBufferUsage("index")),
+        };
+    if index_format != pipeline.index_format {
+        return Err(AFError::IndexFormatMismatch {
+            pipeline: pipeline.index_format,
+            data: index_format,
+        });
+    }
+
+    let indices: usize = index_buffer.byte_size as usize / index_size(index_format);
+    if indices < vertex_count as usize {
+        return Err(AFError::NotEnoughIndices {
+            vertex_count,
+            indices,
+        });
+    }
+
+    let vertices: Option<u64> = pipeline
+        .vertex_strides
+        .iter()
+        .zip(vertex_buffers.iter())
+        .filter_map(|(stride, buffer)| match stride {
+            Some(stride) if *stride > 0 => Some(buffer.byte_size / stride),
+            _ => None,
+        })
+        .min();
+    // only the indices drawn are read, so stale ones after them don't matter
+    if let Some(vertices) = vertices {
+        let size: usize = index_size(index_format);
+        if let Some(index) = index_data.borrow()[..vertex_count as usize * size]
+            .chunks(size)
+            .map(|index| read_index(index_format, index))
+            .find(|index| *index as u64 >= vertices)
+        {
+            return Err(AFError::IndexOutOfRange { index, vertices });
+        }
+    }
+
+    return Ok(());
+}
+
+fn check_pass_format(
+    commands: &[AFRenderCommand],
+    pass_format: &AFPassFormat,
+) -> Result<(), AFError> {
+    // empty commands only clear, so they fit any target
+    return match commands.iter().all(|command| match command.command_type {
+        AFRenderCommandType::Empty => true,
+        _ => command.pass_format == *pass_format,
+    }) {
+        true => Ok(()),
+        false => Err(AFError::TargetMismatch),
+    };
+}
+
+fn render_target_pass_format(config: &AFRenderTargetConfig) -> AFPassFormat {
+    return AFPassFormat {
+        colour_formats: config
+            .formats
+            .iter()
+            .map(|format| texture_format(*format))
+            .collect::<Vec<_>>(),
+        depth_format: config.depth_format.map(depth_format),
+        sample_count: 1,
+    };
+}
+
+impl AFRenderTargetConstructor<AFContext> for AFRenderTarget {
+    fn new(context: &AFContext, config: &AFRenderTargetConfig) -> Result<Self, AFError> {
+        let pass_format: AFPassFormat = render_target_pass_format(config);
+
+        let colours: Vec<AFTexture> = pass_format
+            .colour_formats
+            .iter()
+            .map(|format| {
+                let texture: Texture = context.device.create_texture(&TextureDescriptor {
+                    size: Extent3d {
+                        width: config.size.width,
+                        height: config.size.height,
+                        depth: 1,
+                    },
+                    array_layer_count: 1,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: *format,
+                    usage: TextureUsage::OUTPUT_ATTACHMENT
+                        | TextureUsage::SAMPLED
+                        | TextureUsage::COPY_SRC,
+                });
+                let view: TextureView = texture.create_default_view();
+
+                AFTexture {
+                    texture: Rc::new(texture),
+                    view: Rc::new(view),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        return Ok(AFRenderTarget {
+            colours,
+            depth_target: pass_format
+                .depth_format
+                .map(|format| create_depth_target(&context.device, format, config.size, 1)),
+            pass_format,
+        });
+    }
+}
+
+impl AFRenderTarget {
+    // shares a colour attachment, to be bound to a pipeline's texture slot
+    pub fn texture(&self, attachment: usize) -> Option<AFTexture> {
+        return self.colours.get(attachment).map(|colour| AFTexture {
+            texture: colour.texture.clone(),
+            view: colour.view.clone(),
+        });
+    }
+
+    pub fn attachment_count(&self) -> usize {
+        return self.colours.len();
+    }
+}
+
+impl AFComputePipelineConstructor<AFContext, AFShaderModule> for AFComputePipeline {
+    fn new(
+        context: &AFContext,
+        config: &AFComputePipelineConfig<AFShaderModule>,
+    ) -> Result<Self, AFError> {
+        check_shader_stage(config.compute_shader, AFShaderStage::Compute)?;
+        check_shader_bindings(config.compute_shader, config.uniforms)?;
+
+        let bindings: AFBindings = AFBindings::new(context, config.uniforms);
+
+        let pipeline_layout: PipelineLayout =
+            context
+                .device
+                .create_pipeline_layout(&PipelineLayoutDescriptor {
+                    bind_group_layouts: bindings
+                        .bind_group_layouts
+                        .iter()
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                });
+
+        let compute_pipeline: ComputePipeline =
+            context
+                .device
+                .create_compute_pipeline(&ComputePipelineDescriptor {
+                    layout: &pipeline_layout,
+                    compute_stage: ProgrammableStageDescriptor {
+                        module: &config.compute_shader.module,
+                        entry_point: config.compute_shader.entry.as_str(),
+                    },
+                });
+
+        return Ok(AFComputePipeline {
+            compute_pipeline: Rc::new(compute_pipeline),
+            bindings,
+        });
+    }
+}
+
+impl AFComputeCommandConstructor<AFComputePipeline> for AFComputeCommand {
+    fn new(pipeline: &AFComputePipeline, config: &AFComputeCommandConfig) -> Result<Self, AFError> {
+        pipeline
+            .bindings
+            .check_enabled_groups(&config.enabled_uniform_groups)?;
+        let dynamic_offsets: Vec<Vec<u64>> =
+            pipeline.bindings.dynamic_offsets(config.dynamic_slots.as_slice())?;
+
+        return Ok(AFComputeCommand {
+            compute_pipeline: pipeline.compute_pipeline.clone(),
+            bind_groups: pipeline.bindings.bind_groups.clone(),
+            enabled_uniform_groups: config.enabled_uniform_groups.clone(),
+            dynamic_offsets,
+            workgroups: config.workgroups,
+        });
+    }
+}
+
+// records each command as its own render pass into the view; only the first
+// pass clears, so that later commands draw over the earlier ones.
+fn encode_render_commands(
+    encoder: &mut CommandEncoder,
+    attachments: &AFAttachments,
+    commands: &[AFRenderCommand],
+) {
+    for (i, command) in commands.iter().enumerate() {
+        let (vertex_buffers, index_buffer): (&[AFBuffer], Option<&AFBuffer>) =
+            match &command.command_type {
+                AFRenderCommandType::Empty => (&[], None),
+                AFRenderCommandType::Vertex { vertex_buffers } => (vertex_buffers, None),
+                AFRenderCommandType::Indices {
+                    vertex_buffers,
+                    index_buffer,
+                } => (vertex_buffers, Some(index_buffer)),
+            };
+
+        let load_op: LoadOp = match i {
+            0 => LoadOp::Clear,
+            _ => LoadOp::Load,
+        };
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                color_attachments: attachments
+                    .colours
+                    .iter()
+                    .map(|(view, resolve)| RenderPassColorAttachmentDescriptor {
+                        attachment: view,
+                        resolve_target: *resolve,
+                        load_op,
+                        store_op: StoreOp::Store,
+                        clear_color: Color {
+                            r: command.colour[0],
+                            g: command.colour[1],
+                            b: command.colour[2],
+                            a: command.colour[3],
+                        },
+                    })
+                    .collect::<Vec<_>>()
+                    .as_slice(),
+                depth_stencil_attachment: attachments.depth.map(|depth_view| {
+                    RenderPassDepthStencilAttachmentDescriptor {
+                        attachment: depth_view,
+                        depth_load_op: load_op,
+                        depth_store_op: StoreOp::Store,
+                        clear_depth: command.clear_depth,
+                        stencil_load_op: load_op,
+                        stencil_store_op: StoreOp::Store,
+                        clear_stencil: command.clear_stencil,
+                    }
+                }),
+            });
+
+            match &command.command_type {
+                AFRenderCommandType::Empty => {}
+                _ => {
+                    render_pass.set_pipeline(&command.render_pipeline);
+                    render_pass.set_blend_color(Color {
+                        r: command.blend_colour[0],
+                        g: command.blend_colour[1],
+                        b: command.blend_colour[2],
+                        a: command.blend_colour[3],
+                    });
+                    render_pass.set_stencil_reference(command.stencil_reference);
+                    if let Some(viewport) = command.viewport {
+                        render_pass.set_viewport(
+                            viewport.x,
+                            viewport.y,
+                            viewport.width,
+                            viewport.height,
+                            viewport.min_depth,
+                            viewport.max_depth,
+                        );
+                    }
+                    if let Some(scissor_rect) = command.scissor_rect {
+                        render_pass.set_scissor_rect(
+                            scissor_rect.x,
+                            scissor_rect.y,
+                            scissor_rect.width,
+                            scissor_rect.height,
+                        );
+                    }
+                    for set in command.enabled_uniform_groups.clone() {
+                        render_pass.set_bind_group(
+                            set,
+                            &command.bind_groups[set as usize],
+                            command.dynamic_offsets[set as usize].as_slice(),
+                        );
+                    }
+                    render_pass.set_vertex_buffers(
+                        0,
+                        vertex_buffers
+                            .iter()
+                            .map(|buffer| (buffer.buffer.as_ref(), 0))
+                            .collect::<Vec<_>>()
+                            .as_slice(),
+                    );
+
+                    match index_buffer {
+                        None => {
+                            render_pass.draw(0..command.vertex_count, 0..command.calls);
+                        }
+                        Some(index_buffer) => {
+                            render_pass.set_index_buffer(&index_buffer.buffer, 0);
+                            render_pass.draw_indexed(
+                                0..command.vertex_count,
+                                0,
+                                0..command.calls,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AFMainloop<AFRenderCommand> for AFContext {
+    // a headless context has no events to wait for, so its mainloop
+    // draws frames back to back until the window is destroyed
+    fn mainloop<F, T>(mut context: AFContext, on_redraw: F, on_finish: T)
+    where
+        F: 'static + Fn(&AFContext, &AFMainloopState) -> AFMainloopResult<AFRenderCommand>,
+        T: 'static + Fn(),
+    {
+        let mut state: AFMainloopState = AFMainloopState {
+            size: context.size,
+            focused: false,
+            close_requested: false,
+            was_resized: false,
+            hovered_files: Vec::new(),
+            dropped_files: Vec::new(),
+            pressed_keys: Vec::new(),
+            just_pressed_keys: Vec::new(),
+            cursor_position: AFSize2D {
+                width: 0,
+                height: 0,
+            },
+            draw_error: None,
+        };
+
+        let event_loop: EventLoop<()> = match context.event_loop.take() {
+            Some(event_loop) => event_loop,
+            None => {
+                while run_frame(&mut context, &mut state, &on_redraw) {}
+                on_finish();
+                return;
+            }
+        };
+
+        event_loop.run(move |event, _, control_flow|{
+            *control_flow = ControlFlow::Poll;
+
+            match event {
+                Event::WindowEvent {event, ..} => {
+                    update_mainloop_state(&mut state, &event);
+
+                    match event {
+                        WindowEvent::Resized(size) => {
+                            context.resize_frame(AFSize2D {
+                                width: size.width,
+                                height: size.height,
+                            });
+                        },
+                        WindowEvent::ScaleFactorChanged {new_inner_size, ..} => {
+                            context.resize_frame(AFSize2D {
+                                width: new_inner_size.width,
+                                height: new_inner_size.height,
+                            });
+                        },
+                        _ => {},
+                    }
+                },
+                Event::MainEventsCleared => {
+                    if let AFFrameTarget::Window {window, ..} = &context.target {
+                        window.request_redraw();
+                    }
+                },
+                Event::RedrawRequested(_) => {
+                    *control_flow = match run_frame(&mut context, &mut state, &on_redraw) {
+                        true => ControlFlow::Poll,
+                        false => ControlFlow::Exit,
+                    };
+                },
+                Event::LoopDestroyed => {
+                    on_finish();
+                },
+                _ => {},
+            }
+        });
+    }
+}
+
+// runs the mainloop closure once and draws its commands;
+// returns false once the window has been destroyed
+fn run_frame<F>(context: &mut AFContext, state: &mut AFMainloopState, on_redraw: &F) -> bool
+where
+    F: Fn(&AFContext, &AFMainloopState) -> AFMainloopResult<AFRenderCommand>,
+{
+    let result: AFMainloopResult<AFRenderCommand> = on_redraw(context, state);
+
+    state.close_requested = false;
+    state.was_resized = false;
+    state.dropped_files.clear();
+    state.just_pressed_keys.clear();
+
+    for window_command in result.window_commands {
+        match window_command {
+            AFWindowCommand::UpdateSurface => {
+                context.update_surface();
+            }
+            AFWindowCommand::ResizeWindow(size) => {
+                context.resize_window(size);
+                state.size = size;
+                state.was_resized = true;
+            }
+            AFWindowCommand::DestroyWindow => {
+                return false;
+            }
+        }
+    }
+
+    // handed to the closure next frame rather than panicking inside the event loop
+    state.draw_error = context.draw(result.render_commands.as_slice()).err();
+    context.poll(false);
+
+    return true;
+}
+
+// tracks the window events between frames
+fn update_mainloop_state(state: &mut AFMainloopState, event: &WindowEvent) {
+    match event {
+        WindowEvent::Resized(size) => {
+            state.size = AFSize2D {
+                width: size.width,
+                height: size.height,
+            };
+            state.was_resized = true;
+        }
+        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+            state.size = AFSize2D {
+                width: new_inner_size.width,
+                height: new_inner_size.height,
+            };
+            state.was_resized = true;
+        }
+        WindowEvent::CloseRequested => {
+            state.close_requested = true;
+        }
+        WindowEvent::Focused(focused) => {
+            state.focused = *focused;
+
+            // key releases are not sent to an unfocused window
+            if !focused {
+                state.pressed_keys.clear();
+            }
+        }
+        WindowEvent::HoveredFile(path) => {
+            state.hovered_files.push(path.clone());
+        }
+        WindowEvent::HoveredFileCancelled => {
+            state.hovered_files.clear();
+        }
+        WindowEvent::DroppedFile(path) => {
+            state.hovered_files.clear();
+            state.dropped_files.push(path.clone());
+        }
+        WindowEvent::KeyboardInput { input, .. } => {
+            if let Some(key) = input.virtual_keycode {
+                match input.state {
+                    ElementState::Pressed => {
+                        // held keys repeat their press events
+                        if !state.pressed_keys.contains(&key) {
+                            state.pressed_keys.push(key);
+                            state.just_pressed_keys.push(key);
+                        }
+                    }
+                    ElementState::Released => {
+                        state.pressed_keys.retain(|pressed| *pressed != key);
+                    }
+                }
+            }
+        }
+        WindowEvent::CursorMoved { position, .. } => {
+            state.cursor_position = AFSize2D {
+                width: position.x,
+                height: position.y,
+            };
+        }
+        _ => {}
+    }
+}
+
+// conversions
+
+fn shader_stage(stage: AFShaderStage) -> ShaderStage {
+    return match stage {
+        AFShaderStage::None => ShaderStage::NONE,
+        AFShaderStage::Vertex => ShaderStage::VERTEX,
+        AFShaderStage::Fragment => ShaderStage::FRAGMENT,
+        AFShaderStage::Compute => ShaderStage::COMPUTE,
+    };
+}
+
+fn texture_format(format: AFTextureFormat) -> TextureFormat {
+    return match format {
+        AFTextureFormat::R8Unorm => TextureFormat::R8Unorm,
+        AFTextureFormat::Rg8Unorm => TextureFormat::Rg8Unorm,
+        AFTextureFormat::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+        AFTextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8UnormSrgb,
+        AFTextureFormat::Bgra8Unorm => TextureFormat::Bgra8Unorm,
+        AFTextureFormat::Bgra8UnormSrgb => TextureFormat::Bgra8UnormSrgb,
+        AFTextureFormat::Rgb10a2Unorm => TextureFormat::Rgb10a2Unorm,
+        AFTextureFormat::R16Float => TextureFormat::R16Float,
+        AFTextureFormat::Rg16Float => TextureFormat::Rg16Float,
+        AFTextureFormat::Rgba16Float => TextureFormat::Rgba16Float,
+        AFTextureFormat::R32Float => TextureFormat::R32Float,
+        AFTextureFormat::Rg32Float => TextureFormat::Rg32Float,
+        AFTextureFormat::Rgba32Float => TextureFormat::Rgba32Float,
+        AFTextureFormat::R32UnsignedInt => TextureFormat::R32Uint,
+        AFTextureFormat::R32Int => TextureFormat::R32Sint,
+    };
+}
+
+fn vertex_format(format: AFVertexFormat) -> VertexFormat {
+    return match format {
+        AFVertexFormat::UnsignedChar2 => VertexFormat::Uchar2,
+        AFVertexFormat::UnsignedChar4 => VertexFormat::Uchar4,
+        AFVertexFormat::Char2 => VertexFormat::Char2,
+        AFVertexFormat::Char4 => VertexFormat::Char4,
+        AFVertexFormat::UnsignedChar2Normalized => VertexFormat::Uchar2Norm,
+        AFVertexFormat::UnsignedChar4Normalized => VertexFormat::Uchar4Norm,
+        AFVertexFormat::Char2Normalized => VertexFormat::Char2Norm,
+        AFVertexFormat::Char4Normalized => VertexFormat::Char4Norm,
+        AFVertexFormat::UnsignedShort2 => VertexFormat::Ushort2,
+        AFVertexFormat::UnsignedShort4 => VertexFormat::Ushort4,
+        AFVertexFormat::Short2 => VertexFormat::Short2,
+        AFVertexFormat::Short4 => VertexFormat::Short4,
+        AFVertexFormat::UnsignedShort2Normalized => VertexFormat::Ushort2Norm,
+        AFVertexFormat::UnsignedShort4Normalized => VertexFormat::Ushort4Norm,
+        AFVertexFormat::Short2Normalized => VertexFormat::Short2Norm,
+        AFVertexFormat::Short4Normalized => VertexFormat::Short4Norm,
+        AFVertexFormat::Half2 => VertexFormat::Half2,
+        AFVertexFormat::Half4 => VertexFormat::Half4,
+        AFVertexFormat::Float => VertexFormat::Float,
+        AFVertexFormat::Float2 => VertexFormat::Float2,
+        AFVertexFormat::Float3 => VertexFormat::Float3,
+        AFVertexFormat::Float4 => VertexFormat::Float4,
+        AFVertexFormat::UnsignedInt => VertexFormat::Uint,
+        AFVertexFormat::UnsignedInt2 => VertexFormat::Uint2,
+        AFVertexFormat::UnsignedInt3 => VertexFormat::Uint3,
+        AFVertexFormat::UnsignedInt4 => VertexFormat::Uint4,
+        AFVertexFormat::Int => VertexFormat::Int,
+        AFVertexFormat::Int2 => VertexFormat::Int2,
+        AFVertexFormat::Int3 => VertexFormat::Int3,
+        AFVertexFormat::Int4 => VertexFormat::Int4,
+    };
+}
+
+fn blend_descriptor(blend: &AFBlendDescriptor) -> BlendDescriptor {
+    return BlendDescriptor {
+        src_factor: blend_factor(blend.src_factor),
+        dst_factor: blend_factor(blend.dst_factor),
+        operation: match blend.operation {
+            AFBlendOperation::Add => BlendOperation::Add,
+            AFBlendOperation::Subtract => BlendOperation::Subtract,
+            AFBlendOperation::ReverseSubtract => BlendOperation::ReverseSubtract,
+            AFBlendOperation::Min => BlendOperation::Min,
+            AFBlendOperation::Max => BlendOperation::Max,
+        },
+    };
+}
+
+fn blend_factor(factor: AFBlendFactor) -> BlendFactor {
+    return match factor {
+        AFBlendFactor::Zero => BlendFactor::Zero,
+        AFBlendFactor::One => BlendFactor::One,
+        AFBlendFactor::SrcColour => BlendFactor::SrcColor,
+        AFBlendFactor::OneMinusSrcColour => BlendFactor::OneMinusSrcColor,
+        AFBlendFactor::SrcAlpha => BlendFactor::SrcAlpha,
+        AFBlendFactor::OneMinusSrcAlpha => BlendFactor::OneMinusSrcAlpha,
+        AFBlendFactor::DstColour => BlendFactor::DstColor,
+        AFBlendFactor::OneMinusDstColour => BlendFactor::OneMinusDstColor,
+        AFBlendFactor::DstAlpha => BlendFactor::DstAlpha,
+        AFBlendFactor::OneMinusDstAlpha => BlendFactor::OneMinusDstAlpha,
+        AFBlendFactor::SrcAlphaSaturated => BlendFactor::SrcAlphaSaturated,
+        AFBlendFactor::BlendColour => BlendFactor::BlendColor,
+        AFBlendFactor::OneMinusBlendColour => BlendFactor::OneMinusBlendColor,
+    };
+}
+
+fn filter_mode(mode: AFFilterMode) -> FilterMode {
+    return match mode {
+        AFFilterMode::Nearest => FilterMode::Nearest,
+        AFFilterMode::Linear => FilterMode::Linear,
+    };
+}
+
+fn address_mode(mode: AFAddressMode) -> AddressMode {
+    return match mode {
+        AFAddressMode::ClampToEdge => AddressMode::ClampToEdge,
+        AFAddressMode::Repeat => AddressMode::Repeat,
+        AFAddressMode::MirrorRepeat => AddressMode::MirrorRepeat,
+    };
+}
+
+fn depth_format(format: AFDepthFormat) -> TextureFormat {
+    return match format {
+        AFDepthFormat::Depth32Float => TextureFormat::Depth32Float,
+        AFDepthFormat::Depth24Plus => TextureFormat::Depth24Plus,
+        AFDepthFormat::Depth24PlusStencil8 => TextureFormat::Depth24PlusStencil8,
+    };
+}
+
+fn compare_function(function: AFCompareFunction) -> CompareFunction {
+    return match function {
+        AFCompareFunction::Never => CompareFunction::Never,
+        AFCompareFunction::Less => CompareFunction::Less,
+        AFCompareFunction::Equal => CompareFunction::Equal,
+        AFCompareFunction::LessEqual => CompareFunction::LessEqual,
+        AFCompareFunction::Greater => CompareFunction::Greater,
+        AFCompareFunction::NotEqual => CompareFunction::NotEqual,
+        AFCompareFunction::GreaterEqual => CompareFunction::GreaterEqual,
+        AFCompareFunction::Always => CompareFunction::Always,
+    };
+}
+
+fn stencil_face(face: &AFStencilFaceDescriptor) -> StencilStateFaceDescriptor {
+    return StencilStateFaceDescriptor {
+        compare: compare_function(face.compare),
+        fail_op: stencil_operation(face.fail_op),
+        depth_fail_op: stencil_operation(face.depth_fail_op),
+        pass_op: stencil_operation(face.pass_op),
+    };
+}
+
+fn stencil_operation(operation: AFStencilOperation) -> StencilOperation {
+    return match operation {
+        AFStencilOperation::Keep => StencilOperation::Keep,
+        AFStencilOperation::Zero => StencilOperation::Zero,
+        AFStencilOperation::Replace => StencilOperation::Replace,
+        AFStencilOperation::Invert => StencilOperation::Invert,
+        AFStencilOperation::IncrementClamp => StencilOperation::IncrementClamp,
+        AFStencilOperation::DecrementClamp => StencilOperation::DecrementClamp,
+        AFStencilOperation::IncrementWrap => StencilOperation::IncrementWrap,
+        AFStencilOperation::DecrementWrap => StencilOperation::DecrementWrap,
+    };
+}
+
+fn colour_write_mask(mask: AFColourWriteMask) -> ColorWrite {
+    let mut write_mask: ColorWrite = ColorWrite::empty();
+    if mask.red {
+        write_mask |= ColorWrite::RED;
+    }
+    if mask.green {
+        write_mask |= ColorWrite::GREEN;
+    }
+    if mask.blue {
+        write_mask |= ColorWrite::BLUE;
+    }
+    if mask.alpha {
+        write_mask |= ColorWrite::ALPHA;
+    }
+
+    return write_mask;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_uploads_into_one_chunk() {
+        let writes: [(u64, u64); 3] = [(0, 16), (16, 8), (24, 40)];
+        assert_eq!(batch_uploads(&writes, 64), vec![(0..3, 0..64)]);
+    }
+
+    #[test]
+    fn spills_uploads_into_another_chunk() {
+        let writes: [(u64, u64); 3] = [(0, 32), (32, 24), (56, 16)];
+        assert_eq!(
+            batch_uploads(&writes, 64),
+            vec![(0..2, 0..56), (2..3, 56..72)]
+        );
+    }
+
+    #[test]
+    fn gives_big_uploads_their_own_chunk() {
+        let writes: [(u64, u64); 3] = [(0, 8), (8, 100), (108, 4)];
+        assert_eq!(
+            batch_uploads(&writes, 64),
+            vec![(0..1, 0..8), (1..2, 8..108), (2..3, 108..112)]
+        );
     }
 }
@@ -1,3 +1,6 @@
+#![allow(non_snake_case)] // the crate name
+#![allow(clippy::needless_return)]
+
 // each module depends on
 // each of those before it
 
@@ -5,4 +8,5 @@ pub mod constructors;
 pub mod enums;
 pub mod generic;
 pub mod implementation;
+pub mod reflection;
 pub mod util_structs;
@@ -0,0 +1,785 @@
+use crate::enums::*;
+
+use std::collections::HashMap;
+
+// the instructions, decorations and storage classes read from SPIR-V
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_OUTPUT: u32 = 3;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+// how deep types can nest before they're taken to contain themselves,
+// which only malformed SPIR-V can do
+const MAX_TYPE_DEPTH: u32 = 64;
+
+// far more locations than any device has, so only a malformed
+// array length gives a variable more than this
+const MAX_LOCATIONS: u32 = 4096;
+
+// what an input or output of a shader's entry point holds at one location;
+// matrices and arrays take one location per column or element
+#[derive(Copy, Clone, Debug)]
+pub struct AFShaderVariable {
+    pub location: u32,
+    // None for types which aren't a scalar or a vector, like structs
+    pub scalar_type: Option<AFShaderScalarType>,
+    pub components: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct AFShaderBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub binding_type: AFShaderBindingType,
+    // the size of a buffer's block, leaving out an array of runtime size at its end
+    pub block_size: Option<u64>,
+    pub runtime_sized: bool,
+}
+
+// what the entry point of a shader module reads and writes
+#[derive(Clone, Debug)]
+pub struct AFShaderReflection {
+    pub stage: AFShaderStage,
+    pub inputs: Vec<AFShaderVariable>,
+    pub outputs: Vec<AFShaderVariable>,
+    pub bindings: Vec<AFShaderBinding>,
+}
+
+enum SpirvType {
+    Scalar(AFShaderScalarType, u32),
+    Vector(u32, u32),
+    Matrix(u32, u32),
+    // the element type and the id of the constant holding the length
+    Array(u32, u32),
+    RuntimeArray(u32),
+    Struct(Vec<u32>),
+    Pointer(u32),
+    // 2 if it's only used for storage
+    Image(u32),
+    Sampler,
+    SampledImage,
+}
+
+struct SpirvVariable {
+    id: u32,
+    pointer_type: u32,
+    storage_class: u32,
+}
+
+struct SpirvEntryPoint {
+    execution_model: u32,
+    name: String,
+    interface: Vec<u32>,
+}
+
+#[derive(Default)]
+struct SpirvModule {
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    // the first literal of each decoration, keyed by id and decoration
+    decorations: HashMap<(u32, u32), u32>,
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+    variables: Vec<SpirvVariable>,
+    entry_points: Vec<SpirvEntryPoint>,
+}
+
+impl AFShaderReflection {
+    // reflects the named entry point of the SPIR-V words
+    pub fn new(spirv: &[u32], entry_point: &str) -> Result<Self, AFError> {
+        let module: SpirvModule = parse_spirv(spirv)?;
+
+        let entry: &SpirvEntryPoint = match module
+            .entry_points
+            .iter()
+            .find(|entry| entry.name == entry_point)
+        {
+            Some(entry) => entry,
+            None => return Err(AFError::NoEntryPoint(entry_point.to_string())),
+        };
+        let stage: AFShaderStage = match entry.execution_model {
+            0 => AFShaderStage::Vertex,
+            4 => AFShaderStage::Fragment,
+            5 => AFShaderStage::Compute,
+            _ => AFShaderStage::None,
+        };
+
+        let mut inputs: Vec<AFShaderVariable> = Vec::new();
+        let mut outputs: Vec<AFShaderVariable> = Vec::new();
+        let mut bindings: Vec<AFShaderBinding> = Vec::new();
+        for variable in module.variables.iter() {
+            let pointee: u32 = match module.types.get(&variable.pointer_type) {
+                Some(SpirvType::Pointer(pointee)) => *pointee,
+                _ => return Err(spirv_error("a variable's type isn't a pointer")),
+            };
+
+            match variable.storage_class {
+                STORAGE_INPUT | STORAGE_OUTPUT => {
+                    // built ins like gl_Position have no location
+                    let location: u32 =
+                        match module.decorations.get(&(variable.id, DECORATION_LOCATION)) {
+                            Some(location) if entry.interface.contains(&variable.id) => *location,
+                            _ => continue,
+                        };
+                    let mut shader_variables: Vec<AFShaderVariable> = Vec::new();
+                    module.shader_variables(location, pointee, 0, &mut shader_variables)?;
+                    match variable.storage_class {
+                        STORAGE_INPUT => inputs.extend(shader_variables),
+                        _ => outputs.extend(shader_variables),
+                    }
+                }
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let binding: u32 =
+                        match module.decorations.get(&(variable.id, DECORATION_BINDING)) {
+                            Some(binding) => *binding,
+                            None => continue,
+                        };
+                    let set: u32 = module
+                        .decorations
+                        .get(&(variable.id, DECORATION_DESCRIPTOR_SET))
+                        .cloned()
+                        .unwrap_or(0);
+                    if let Some(shader_binding) =
+                        module.shader_binding(set, binding, variable.storage_class, pointee)?
+                    {
+                        bindings.push(shader_binding);
+                    }
+                }
+                _ => {}
+            }
+        }
+        inputs.sort_by_key(|input| input.location);
+        outputs.sort_by_key(|output| output.location);
+        bindings.sort_by_key(|binding| (binding.set, binding.binding));
+
+        return Ok(AFShaderReflection {
+            stage,
+            inputs,
+            outputs,
+            bindings,
+        });
+    }
+
+    pub fn input(&self, location: u32) -> Option<&AFShaderVariable> {
+        return self.inputs.iter().find(|input| input.location == location);
+    }
+
+    pub fn binding(&self, set: u32, binding: u32) -> Option<&AFShaderBinding> {
+        return self
+            .bindings
+            .iter()
+            .find(|shader_binding| shader_binding.set == set && shader_binding.binding == binding);
+    }
+}
+
+impl SpirvModule {
+    // adds what the type holds at each location it takes up, starting from the given one
+    fn shader_variables(
+        &self,
+        location: u32,
+        type_id: u32,
+        depth: u32,
+        shader_variables: &mut Vec<AFShaderVariable>,
+    ) -> Result<(), AFError> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(type_depth_error());
+        }
+        if location >= MAX_LOCATIONS {
+            return Err(spirv_error("a variable takes up too many locations"));
+        }
+
+        let (element, count): (u32, u32) = match self.types.get(&type_id) {
+            Some(SpirvType::Matrix(column, count)) => (*column, *count),
+            Some(SpirvType::Array(element, length)) => match self.constants.get(length) {
+                Some(length) => (*element, *length),
+                None => return Err(spirv_error("an array's length isn't a constant")),
+            },
+            Some(SpirvType::Scalar(scalar_type, _)) => {
+                shader_variables.push(AFShaderVariable {
+                    location,
+                    scalar_type: Some(*scalar_type),
+                    components: 1,
+                });
+                return Ok(());
+            }
+            Some(SpirvType::Vector(component, count)) => {
+                shader_variables.push(AFShaderVariable {
+                    location,
+                    scalar_type: match self.types.get(component) {
+                        Some(SpirvType::Scalar(scalar_type, _)) => Some(*scalar_type),
+                        _ => None,
+                    },
+                    components: *count,
+                });
+                return Ok(());
+            }
+            _ => {
+                shader_variables.push(AFShaderVariable {
+                    location,
+                    scalar_type: None,
+                    components: 0,
+                });
+                return Ok(());
+            }
+        };
+
+        // each element starts after the locations of the one before it
+        let mut next_location: u32 = location;
+        for _ in 0..count {
+            self.shader_variables(next_location, element, depth + 1, shader_variables)?;
+            next_location = match shader_variables.last() {
+                Some(last) => last.location + 1,
+                None => next_location,
+            };
+        }
+
+        return Ok(());
+    }
+
+    fn shader_binding(
+        &self,
+        set: u32,
+        binding: u32,
+        storage_class: u32,
+        type_id: u32,
+    ) -> Result<Option<AFShaderBinding>, AFError> {
+        // arrays of textures or samplers take the type of their elements
+        let mut type_id: u32 = type_id;
+        let mut depth: u32 = 0;
+        while let Some(SpirvType::Array(element, _)) | Some(SpirvType::RuntimeArray(element)) =
+            self.types.get(&type_id)
+        {
+            type_id = *element;
+            depth += 1;
+            if depth > MAX_TYPE_DEPTH {
+                return Err(type_depth_error());
+            }
+        }
+
+        let binding_type: AFShaderBindingType = match (storage_class, self.types.get(&type_id)) {
+            (STORAGE_UNIFORM, Some(SpirvType::Struct(_))) => {
+                match self
+                    .decorations
+                    .contains_key(&(type_id, DECORATION_BUFFER_BLOCK))
+                {
+                    true => AFShaderBindingType::StorageBuffer,
+                    false => AFShaderBindingType::UniformBuffer,
+                }
+            }
+            (STORAGE_STORAGE_BUFFER, Some(SpirvType::Struct(_))) => {
+                AFShaderBindingType::StorageBuffer
+            }
+            (_, Some(SpirvType::Sampler)) => AFShaderBindingType::Sampler,
+            (_, Some(SpirvType::Image(2))) => AFShaderBindingType::StorageTexture,
+            (_, Some(SpirvType::Image(_))) | (_, Some(SpirvType::SampledImage)) => {
+                AFShaderBindingType::Texture
+            }
+            _ => return Ok(None),
+        };
+
+        let (block_size, runtime_sized): (Option<u64>, bool) = match binding_type {
+            AFShaderBindingType::UniformBuffer | AFShaderBindingType::StorageBuffer => {
+                let runtime_sized: bool = match self.types.get(&type_id) {
+                    Some(SpirvType::Struct(members)) => match members.last() {
+                        Some(last) => {
+                            matches!(self.types.get(last), Some(SpirvType::RuntimeArray(_)))
+                        }
+                        None => false,
+                    },
+                    _ => false,
+                };
+                (Some(self.type_size(type_id, 0)?), runtime_sized)
+            }
+            _ => (None, false),
+        };
+
+        return Ok(Some(AFShaderBinding {
+            set,
+            binding,
+            binding_type,
+            block_size,
+            runtime_sized,
+        }));
+    }
+
+    // the bytes a type takes up in a buffer, by its explicit layout
+    fn type_size(&self, type_id: u32, depth: u32) -> Result<u64, AFError> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(type_depth_error());
+        }
+
+        return Ok(match self.types.get(&type_id) {
+            Some(SpirvType::Scalar(_, width)) => *width as u64 / 8,
+            Some(SpirvType::Vector(component, count)) => {
+                size_product(self.type_size(*component, depth + 1)?, *count as u64)?
+            }
+            Some(SpirvType::Matrix(column, count)) => {
+                size_product(self.type_size(*column, depth + 1)?, *count as u64)?
+            }
+            Some(SpirvType::Array(element, length)) => {
+                let length: u64 = match self.constants.get(length) {
+                    Some(length) => *length as u64,
+                    None => return Err(spirv_error("an array's length isn't a constant")),
+                };
+                let stride: u64 = match self.decorations.get(&(type_id, DECORATION_ARRAY_STRIDE)) {
+                    Some(stride) => *stride as u64,
+                    None => self.type_size(*element, depth + 1)?,
+                };
+                size_product(stride, length)?
+            }
+            Some(SpirvType::RuntimeArray(_)) => 0,
+            Some(SpirvType::Struct(members)) => {
+                let mut size: u64 = 0;
+                for (i, member) in members.iter().enumerate() {
+                    let offset: u64 = self
+                        .member_decorations
+                        .get(&(type_id, i as u32, DECORATION_OFFSET))
+                        .cloned()
+                        .unwrap_or(0) as u64;
+                    // matrices in blocks are laid out by their stride
+                    let member_size: u64 = match (
+                        self.types.get(member),
+                        self.member_decorations
+                            .get(&(type_id, i as u32, DECORATION_MATRIX_STRIDE)),
+                    ) {
+                        (Some(SpirvType::Matrix(_, count)), Some(stride)) => {
+                            *stride as u64 * *count as u64
+                        }
+                        _ => self.type_size(*member, depth + 1)?,
+                    };
+                    size = size.max(
+                        offset
+                            .checked_add(member_size)
+                            .ok_or_else(type_size_error)?,
+                    );
+                }
+                size
+            }
+            _ => 0,
+        });
+    }
+}
+
+fn spirv_error(reason: &str) -> AFError {
+    return AFError::InvalidSpirv(reason.to_string());
+}
+
+fn type_depth_error() -> AFError {
+    return spirv_error("a type nests too deeply or contains itself");
+}
+
+fn type_size_error() -> AFError {
+    return spirv_error("a type is too big");
+}
+
+fn size_product(size: u64, count: u64) -> Result<u64, AFError> {
+    return size.checked_mul(count).ok_or_else(type_size_error);
+}
+
+fn parse_spirv(spirv: &[u32]) -> Result<SpirvModule, AFError> {
+    if spirv.len() < 5 || spirv[0] != SPIRV_MAGIC {
+        return Err(spirv_error("the header is missing"));
+    }
+
+    let mut module: SpirvModule = SpirvModule::default();
+    let mut i: usize = 5;
+    while i < spirv.len() {
+        let word_count: usize = (spirv[i] >> 16) as usize;
+        let opcode: u32 = spirv[i] & 0xffff;
+        if word_count == 0 || i + word_count > spirv.len() {
+            return Err(spirv_error("an instruction runs past the end"));
+        }
+        let operands: &[u32] = &spirv[i + 1..i + word_count];
+        i += word_count;
+
+        // every instruction read has at least this many operands
+        let needed: usize = match opcode {
+            OP_TYPE_BOOL | OP_TYPE_SAMPLER | OP_TYPE_SAMPLED_IMAGE | OP_TYPE_STRUCT => 1,
+            OP_TYPE_FLOAT | OP_TYPE_RUNTIME_ARRAY | OP_DECORATE => 2,
+            OP_ENTRY_POINT | OP_TYPE_INT | OP_TYPE_VECTOR | OP_TYPE_MATRIX | OP_TYPE_ARRAY
+            | OP_TYPE_POINTER | OP_CONSTANT | OP_VARIABLE | OP_MEMBER_DECORATE => 3,
+            OP_TYPE_IMAGE => 8,
+            _ => 0,
+        };
+        if operands.len() < needed {
+            return Err(spirv_error("an instruction has too few operands"));
+        }
+
+        match opcode {
+            OP_ENTRY_POINT => {
+                let (name, name_words): (String, usize) = read_string(&operands[2..]);
+                module.entry_points.push(SpirvEntryPoint {
+                    execution_model: operands[0],
+                    name,
+                    interface: operands[(2 + name_words).min(operands.len())..].to_vec(),
+                });
+            }
+            OP_TYPE_BOOL => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Scalar(AFShaderScalarType::Bool, 32));
+            }
+            OP_TYPE_INT => {
+                let scalar_type: AFShaderScalarType = match operands[2] {
+                    0 => AFShaderScalarType::UnsignedInt,
+                    _ => AFShaderScalarType::Int,
+                };
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Scalar(scalar_type, operands[1]));
+            }
+            OP_TYPE_FLOAT => {
+                module.types.insert(
+                    operands[0],
+                    SpirvType::Scalar(AFShaderScalarType::Float, operands[1]),
+                );
+            }
+            OP_TYPE_VECTOR => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Vector(operands[1], operands[2]));
+            }
+            OP_TYPE_MATRIX => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Matrix(operands[1], operands[2]));
+            }
+            OP_TYPE_IMAGE => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Image(operands[6]));
+            }
+            OP_TYPE_SAMPLER => {
+                module.types.insert(operands[0], SpirvType::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                module.types.insert(operands[0], SpirvType::SampledImage);
+            }
+            OP_TYPE_ARRAY => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Array(operands[1], operands[2]));
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::RuntimeArray(operands[1]));
+            }
+            OP_TYPE_STRUCT => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Struct(operands[1..].to_vec()));
+            }
+            OP_TYPE_POINTER => {
+                module
+                    .types
+                    .insert(operands[0], SpirvType::Pointer(operands[2]));
+            }
+            OP_CONSTANT => {
+                module.constants.insert(operands[1], operands[2]);
+            }
+            OP_VARIABLE => {
+                module.variables.push(SpirvVariable {
+                    id: operands[1],
+                    pointer_type: operands[0],
+                    storage_class: operands[2],
+                });
+            }
+            OP_DECORATE => {
+                module.decorations.insert(
+                    (operands[0], operands[1]),
+                    operands.get(2).cloned().unwrap_or(0),
+                );
+            }
+            OP_MEMBER_DECORATE => {
+                module.member_decorations.insert(
+                    (operands[0], operands[1], operands[2]),
+                    operands.get(3).cloned().unwrap_or(0),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    return Ok(module);
+}
+
+// a null terminated UTF-8 string packed into words, and the number of words it took up
+fn read_string(words: &[u32]) -> (String, usize) {
+    let mut bytes: Vec<u8> = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes().iter() {
+            if *byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), i + 1);
+            }
+            bytes.push(*byte);
+        }
+    }
+
+    return (String::from_utf8_lossy(&bytes).into_owned(), words.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(bytes: &[u8]) -> Vec<u32> {
+        return bytes
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<_>>();
+    }
+
+    fn vertex_spirv() -> Vec<u32> {
+        return words(include_bytes!("../examples/render/shader.vert.spv"));
+    }
+
+    fn fragment_spirv() -> Vec<u32> {
+        return words(include_bytes!("../examples/render/shader.frag.spv"));
+    }
+
+    // a header followed by the given instructions
+    fn module(instructions: &[u32]) -> Vec<u32> {
+        let mut spirv: Vec<u32> = vec![SPIRV_MAGIC, 0x0001_0000, 0, 100, 0];
+        spirv.extend_from_slice(instructions);
+        return spirv;
+    }
+
+    #[test]
+    fn reflects_vertex_shader() {
+        let reflection: AFShaderReflection =
+            AFShaderReflection::new(&vertex_spirv(), "main").unwrap();
+
+        assert_eq!(reflection.stage, AFShaderStage::Vertex);
+        assert_eq!(reflection.inputs.len(), 1);
+        let input: &AFShaderVariable = reflection.input(0).unwrap();
+        assert_eq!(input.scalar_type, Some(AFShaderScalarType::Float));
+        assert_eq!(input.components, 2);
+        // gl_Position is a built in, so it has no location
+        assert!(reflection.outputs.is_empty());
+
+        assert_eq!(reflection.bindings.len(), 1);
+        let binding: &AFShaderBinding = reflection.binding(0, 0).unwrap();
+        assert_eq!(binding.binding_type, AFShaderBindingType::UniformBuffer);
+        assert_eq!(binding.block_size, Some(4));
+        assert!(!binding.runtime_sized);
+    }
+
+    #[test]
+    fn reflects_fragment_shader() {
+        let reflection: AFShaderReflection =
+            AFShaderReflection::new(&fragment_spirv(), "main").unwrap();
+
+        assert_eq!(reflection.stage, AFShaderStage::Fragment);
+        assert!(reflection.inputs.is_empty());
+        assert_eq!(reflection.outputs.len(), 1);
+        assert_eq!(reflection.outputs[0].location, 0);
+        assert_eq!(
+            reflection.outputs[0].scalar_type,
+            Some(AFShaderScalarType::Float)
+        );
+        assert_eq!(reflection.outputs[0].components, 4);
+        assert!(reflection.bindings.is_empty());
+    }
+
+    #[test]
+    fn missing_entry_point() {
+        let error: AFError = AFShaderReflection::new(&vertex_spirv(), "other").unwrap_err();
+        assert!(matches!(error, AFError::NoEntryPoint(name) if name == "other"));
+    }
+
+    #[test]
+    fn missing_header() {
+        assert!(matches!(
+            AFShaderReflection::new(&[], "main"),
+            Err(AFError::InvalidSpirv(_))
+        ));
+
+        let mut spirv: Vec<u32> = vertex_spirv();
+        spirv[0] = 0;
+        assert!(matches!(
+            AFShaderReflection::new(&spirv, "main"),
+            Err(AFError::InvalidSpirv(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_instruction() {
+        // an OpTypeVector of 4 words with only its first word left
+        assert!(matches!(
+            AFShaderReflection::new(&module(&[(4 << 16) | OP_TYPE_VECTOR]), "main"),
+            Err(AFError::InvalidSpirv(_))
+        ));
+
+        // the shader cut off one word into its first instruction, an OpCapability of 2 words
+        let spirv: Vec<u32> = vertex_spirv();
+        assert!(matches!(
+            AFShaderReflection::new(&spirv[..6], "main"),
+            Err(AFError::InvalidSpirv(_))
+        ));
+
+        // a word count of 0 would never move past the instruction
+        assert!(matches!(
+            AFShaderReflection::new(&module(&[OP_TYPE_FLOAT]), "main"),
+            Err(AFError::InvalidSpirv(_))
+        ));
+    }
+
+    #[test]
+    fn too_few_operands() {
+        // an OpTypeVector without its component count
+        assert!(matches!(
+            AFShaderReflection::new(&module(&[(3 << 16) | OP_TYPE_VECTOR, 1, 2]), "main"),
+            Err(AFError::InvalidSpirv(_))
+        ));
+    }
+
+    // a vertex entry point named main using a uniform variable of type 2 at binding 0
+    fn uniform_module(type_instructions: &[u32]) -> Vec<u32> {
+        let mut instructions: Vec<u32> = vec![
+            (5 << 16) | OP_ENTRY_POINT,
+            0,
+            1,
+            u32::from_le_bytes(*b"main"),
+            0,
+            (4 << 16) | OP_TYPE_POINTER,
+            4,
+            STORAGE_UNIFORM,
+            2,
+            (4 << 16) | OP_VARIABLE,
+            4,
+            5,
+            STORAGE_UNIFORM,
+            (4 << 16) | OP_DECORATE,
+            5,
+            DECORATION_BINDING,
+            0,
+        ];
+        instructions.extend_from_slice(type_instructions);
+        return module(&instructions);
+    }
+
+    #[test]
+    fn array_of_itself() {
+        let spirv: Vec<u32> = uniform_module(&[(4 << 16) | OP_TYPE_ARRAY, 2, 2, 3]);
+        assert!(matches!(
+            AFShaderReflection::new(&spirv, "main"),
+            Err(AFError::InvalidSpirv(reason)) if reason.contains("contains itself")
+        ));
+    }
+
+    #[test]
+    fn struct_containing_itself() {
+        let spirv: Vec<u32> = uniform_module(&[(3 << 16) | OP_TYPE_STRUCT, 2, 2]);
+        assert!(matches!(
+            AFShaderReflection::new(&spirv, "main"),
+            Err(AFError::InvalidSpirv(reason)) if reason.contains("contains itself")
+        ));
+    }
+
+    // a vertex entry point named main reading an input of type 2 at location 3,
+    // with 4 component float vectors as type 7
+    fn input_module(type_instructions: &[u32]) -> Vec<u32> {
+        let mut instructions: Vec<u32> = vec![
+            (6 << 16) | OP_ENTRY_POINT,
+            0,
+            1,
+            u32::from_le_bytes(*b"main"),
+            0,
+            5,
+            (3 << 16) | OP_TYPE_FLOAT,
+            6,
+            32,
+            (4 << 16) | OP_TYPE_VECTOR,
+            7,
+            6,
+            4,
+            (4 << 16) | OP_TYPE_POINTER,
+            4,
+            STORAGE_INPUT,
+            2,
+            (4 << 16) | OP_VARIABLE,
+            4,
+            5,
+            STORAGE_INPUT,
+            (4 << 16) | OP_DECORATE,
+            5,
+            DECORATION_LOCATION,
+            3,
+        ];
+        instructions.extend_from_slice(type_instructions);
+        return module(&instructions);
+    }
+
+    #[test]
+    fn matrix_input_takes_a_location_per_column() {
+        let spirv: Vec<u32> = input_module(&[(4 << 16) | OP_TYPE_MATRIX, 2, 7, 4]);
+        let reflection: AFShaderReflection = AFShaderReflection::new(&spirv, "main").unwrap();
+
+        assert_eq!(reflection.inputs.len(), 4);
+        for (i, input) in reflection.inputs.iter().enumerate() {
+            assert_eq!(input.location, 3 + i as u32);
+            assert_eq!(input.scalar_type, Some(AFShaderScalarType::Float));
+            assert_eq!(input.components, 4);
+        }
+    }
+
+    #[test]
+    fn array_of_matrices_input() {
+        // a 2 element array of 4x4 matrices, its length the constant 8
+        let spirv: Vec<u32> = input_module(&[
+            (4 << 16) | OP_TYPE_INT,
+            9,
+            32,
+            0,
+            (4 << 16) | OP_CONSTANT,
+            9,
+            8,
+            2,
+            (4 << 16) | OP_TYPE_MATRIX,
+            10,
+            7,
+            4,
+            (4 << 16) | OP_TYPE_ARRAY,
+            2,
+            10,
+            8,
+        ]);
+        let reflection: AFShaderReflection = AFShaderReflection::new(&spirv, "main").unwrap();
+
+        let locations: Vec<u32> = reflection
+            .inputs
+            .iter()
+            .map(|input| input.location)
+            .collect::<Vec<_>>();
+        assert_eq!(locations, (3..11).collect::<Vec<_>>());
+    }
+}
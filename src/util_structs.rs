@@ -1,11 +1,218 @@
+use crate::enums::AFError;
+
+#[cfg(feature = "png")]
+use std::path::Path;
+
 #[derive(Copy, Clone)]
 pub struct AFSize2D<T> {
     pub width: T,
     pub height: T,
 }
 
+// RGBA8 pixels, row by row
 #[derive(Copy, Clone)]
 pub struct AFImage<'a> {
     pub size: AFSize2D<u32>,
     pub data: &'a [u8],
 }
+
+impl<'a> AFImage<'a> {
+    pub fn new(size: AFSize2D<u32>, data: &'a [u8]) -> Result<Self, AFError> {
+        let image: AFImage = AFImage { size, data };
+        image.validate()?;
+
+        return Ok(image);
+    }
+
+    // checks that there are exactly four bytes for every pixel
+    pub fn validate(&self) -> Result<(), AFError> {
+        return match self.data.len() == self.size.width as usize * self.size.height as usize * 4 {
+            true => Ok(()),
+            false => Err(AFError::ImageSizeMismatch {
+                width: self.size.width,
+                height: self.size.height,
+                length: self.data.len(),
+            }),
+        };
+    }
+}
+
+// an image which owns its pixels, such as a frame read back from the GPU
+#[derive(Clone)]
+pub struct AFOwnedImage {
+    pub size: AFSize2D<u32>,
+    pub data: Vec<u8>,
+}
+
+impl AFOwnedImage {
+    pub fn new(size: AFSize2D<u32>, data: Vec<u8>) -> Result<Self, AFError> {
+        AFImage::new(size, data.as_slice())?;
+
+        return Ok(AFOwnedImage { size, data });
+    }
+
+    pub fn as_image(&self) -> AFImage<'_> {
+        return AFImage {
+            size: self.size,
+            data: self.data.as_slice(),
+        };
+    }
+}
+
+#[cfg(feature = "png")]
+impl AFOwnedImage {
+    // any PNG colour type or bit depth is converted to RGBA8
+    pub fn from_png_bytes(bytes: &[u8]) -> Result<Self, AFError> {
+        let mut decoder: png::Decoder<&[u8]> = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder
+            .read_info()
+            .map_err(|error| AFError::Png(error.to_string()))?;
+
+        let mut pixels: Vec<u8> = vec![0; info.buffer_size()];
+        reader
+            .next_frame(&mut pixels)
+            .map_err(|error| AFError::Png(error.to_string()))?;
+
+        let data: Vec<u8> = match info.color_type {
+            png::ColorType::RGBA => pixels,
+            png::ColorType::RGB => pixels
+                .chunks(3)
+                .flat_map(|p| vec![p[0], p[1], p[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => pixels
+                .chunks(2)
+                .flat_map(|p| vec![p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Grayscale => pixels.iter().flat_map(|&p| vec![p, p, p, 255]).collect(),
+            png::ColorType::Indexed => {
+                return Err(AFError::Png(String::from("The palette could not be expanded.")));
+            }
+        };
+
+        return AFOwnedImage::new(
+            AFSize2D {
+                width: info.width,
+                height: info.height,
+            },
+            data,
+        );
+    }
+
+    pub fn from_png_file<P: AsRef<Path>>(path: P) -> Result<Self, AFError> {
+        let bytes: Vec<u8> = std::fs::read(path).map_err(|error| AFError::Io(error.to_string()))?;
+
+        return AFOwnedImage::from_png_bytes(bytes.as_slice());
+    }
+}
+
+#[cfg(feature = "png")]
+impl<'a> AFImage<'a> {
+    pub fn to_png_bytes(&self) -> Result<Vec<u8>, AFError> {
+        self.validate()?;
+
+        let mut bytes: Vec<u8> = Vec::new();
+        {
+            let mut encoder: png::Encoder<&mut Vec<u8>> =
+                png::Encoder::new(&mut bytes, self.size.width, self.size.height);
+            encoder.set_color(png::ColorType::RGBA);
+            encoder.set_depth(png::BitDepth::Eight);
+
+            let mut writer: png::Writer<&mut Vec<u8>> = encoder
+                .write_header()
+                .map_err(|error| AFError::Png(error.to_string()))?;
+            writer
+                .write_image_data(self.data)
+                .map_err(|error| AFError::Png(error.to_string()))?;
+        }
+
+        return Ok(bytes);
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), AFError> {
+        let bytes: Vec<u8> = self.to_png_bytes()?;
+
+        return std::fs::write(path, bytes).map_err(|error| AFError::Io(error.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_wrong_length() {
+        let data: [u8; 7] = [0; 7];
+        let size: AFSize2D<u32> = AFSize2D {
+            width: 2,
+            height: 1,
+        };
+        assert!(matches!(
+            AFImage::new(size, &data),
+            Err(AFError::ImageSizeMismatch {
+                width: 2,
+                height: 1,
+                length: 7,
+            })
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "png"))]
+mod png_tests {
+    use super::*;
+
+    // a PNG of the given colour type, 8 bits per channel
+    fn encode(width: u32, height: u32, colour_type: png::ColorType, data: &[u8]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        {
+            let mut encoder: png::Encoder<&mut Vec<u8>> =
+                png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(colour_type);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer: png::Writer<&mut Vec<u8>> = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+
+        return bytes;
+    }
+
+    #[test]
+    fn round_trips_rgba() {
+        let data: Vec<u8> = vec![255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0, 1, 2, 3, 4];
+        let image: AFImage = AFImage::new(
+            AFSize2D {
+                width: 2,
+                height: 2,
+            },
+            data.as_slice(),
+        )
+        .unwrap();
+
+        let decoded: AFOwnedImage =
+            AFOwnedImage::from_png_bytes(image.to_png_bytes().unwrap().as_slice()).unwrap();
+        assert_eq!(decoded.size.width, 2);
+        assert_eq!(decoded.size.height, 2);
+        assert_eq!(decoded.data, data);
+    }
+
+    #[test]
+    fn expands_rgb() {
+        let bytes: Vec<u8> = encode(2, 1, png::ColorType::RGB, &[10, 20, 30, 40, 50, 60]);
+
+        let decoded: AFOwnedImage = AFOwnedImage::from_png_bytes(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.size.width, 2);
+        assert_eq!(decoded.size.height, 1);
+        assert_eq!(decoded.data, vec![10, 20, 30, 255, 40, 50, 60, 255]);
+    }
+
+    #[test]
+    fn expands_grayscale() {
+        let bytes: Vec<u8> = encode(1, 2, png::ColorType::Grayscale, &[7, 200]);
+
+        let decoded: AFOwnedImage = AFOwnedImage::from_png_bytes(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.size.width, 1);
+        assert_eq!(decoded.size.height, 2);
+        assert_eq!(decoded.data, vec![7, 7, 7, 255, 200, 200, 200, 255]);
+    }
+}
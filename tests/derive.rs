@@ -0,0 +1,34 @@
+extern crate AlfredGF_rs;
+
+use AlfredGF_rs::enums::AFVertexFormat;
+use AlfredGF_rs::generic::AFVertex as _;
+use AlfredGF_rs_derive::AFVertex;
+use zerocopy::AsBytes;
+
+#[derive(Copy, Clone, AsBytes, AFVertex)]
+#[repr(C)]
+struct Vertex {
+    position: [f32; 3],
+    #[vertex_format(UnsignedChar4Normalized)]
+    colour: [u8; 4],
+    #[location(5)]
+    uv: [f32; 2],
+}
+
+#[test]
+fn derives_vertex_layout() {
+    assert_eq!(Vertex::STRIDE, 24);
+
+    let attribs: Vec<(u32, u32, AFVertexFormat)> = Vertex::ATTRIBS
+        .iter()
+        .map(|attrib| (attrib.location, attrib.offset, attrib.vertex_format))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attribs,
+        vec![
+            (0, 0, AFVertexFormat::Float3),
+            (1, 12, AFVertexFormat::UnsignedChar4Normalized),
+            (5, 16, AFVertexFormat::Float2),
+        ]
+    );
+}